@@ -0,0 +1,111 @@
+//! Measure the time taken to decode a large TBON-encoded list of `u64`s, and a large byte array.
+//!
+//! Set `TBON_BENCH_MB` to change the size of the encoded data (the default is 100MB).
+//!
+//! For comparison, the list is also decoded from 64KiB chunks using a `Vec<u8>` buffer which is
+//! drained from the front, which is how the [`tbon::de::Decoder`] buffered its input before it
+//! queued incoming chunks. Decoding a single slice this way takes time quadratic in its size, so
+//! it's not measured.
+
+use std::time::Instant;
+
+use bytes::Bytes;
+use futures::executor::block_on;
+use futures::future;
+use futures::stream::{self, TryStreamExt};
+
+const CHUNK_SIZE: usize = 64 * 1024;
+const ELEMENT_SIZE: usize = 9;
+
+fn main() {
+    let size_mb = std::env::var("TBON_BENCH_MB")
+        .map(|size| size.parse().expect("size in MB"))
+        .unwrap_or(100);
+
+    let len = (size_mb * 1024 * 1024 / ELEMENT_SIZE) as u64;
+    let encoded = tbon::en::encode_seq(stream::iter(0..len));
+    let encoded = block_on(encoded.try_fold(Vec::new(), |mut encoded, chunk| {
+        encoded.extend_from_slice(&chunk);
+        future::ready(Ok(encoded))
+    }))
+    .expect("encoded list");
+
+    let start = Instant::now();
+    let decoded: Vec<u64> = tbon::from_slice((), &encoded).expect("decoded list");
+    assert_eq!(decoded.len() as u64, len);
+    report("a single slice", encoded.len(), start);
+
+    let chunks = encoded
+        .chunks(CHUNK_SIZE)
+        .map(Bytes::copy_from_slice)
+        .collect::<Vec<_>>();
+
+    let start = Instant::now();
+    let decoded = decode_with_vec_buffer(&chunks);
+    assert_eq!(decoded.len() as u64, len);
+    report("64KiB chunks (Vec buffer baseline)", encoded.len(), start);
+
+    let start = Instant::now();
+    let decoded: Vec<u64> = block_on(tbon::de::decode((), stream::iter(chunks))).expect("list");
+    assert_eq!(decoded.len() as u64, len);
+    report("64KiB chunks", encoded.len(), start);
+
+    let blob = (0..=255u8)
+        .cycle()
+        .take(size_mb * 1024 * 1024)
+        .collect::<Vec<u8>>();
+
+    let start = Instant::now();
+    let encoded = tbon::to_vec(Bytes::from(blob)).expect("encoded blob");
+    report("a byte array (encode)", encoded.len(), start);
+
+    let start = Instant::now();
+    let decoded: Bytes = tbon::from_slice((), &encoded).expect("decoded blob");
+    assert_eq!(decoded.len(), size_mb * 1024 * 1024);
+    report("a byte array", encoded.len(), start);
+}
+
+/// Decode a list of `u64`s from the given `chunks` by buffering them in a `Vec<u8>` and draining
+/// each value from the front of it, the way the decoder did before it queued incoming chunks.
+fn decode_with_vec_buffer(chunks: &[Bytes]) -> Vec<u64> {
+    let mut chunks = chunks.iter();
+    let mut buffer = Vec::new();
+    let mut decoded = Vec::new();
+
+    loop {
+        while buffer.len() <= ELEMENT_SIZE {
+            match chunks.next() {
+                Some(chunk) => buffer.extend_from_slice(chunk),
+                None => break,
+            }
+        }
+
+        match buffer.first() {
+            Some(b'[') => {
+                buffer.remove(0);
+            }
+            Some(b']') => {
+                buffer.remove(0);
+                break;
+            }
+            Some(_) => {
+                buffer.remove(0); // the type bit
+                let bytes: Vec<u8> = buffer.drain(0..ELEMENT_SIZE - 1).collect();
+                decoded.push(u64::from_be_bytes(bytes.try_into().expect("u64")));
+            }
+            None => panic!("unexpected end of input"),
+        }
+    }
+
+    assert!(buffer.is_empty());
+    decoded
+}
+
+fn report(source: &str, size: usize, start: Instant) {
+    let elapsed = start.elapsed();
+    let throughput = (size as f64 / (1024. * 1024.)) / elapsed.as_secs_f64();
+    println!(
+        "processed {} bytes of {} in {:?} ({:.1} MiB/s)",
+        size, source, elapsed, throughput
+    );
+}
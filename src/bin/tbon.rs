@@ -0,0 +1,153 @@
+//! Inspect and convert TBON-encoded files.
+//!
+//! Each subcommand reads from the given file, or from stdin if no file (or `-`) is given,
+//! and writes to stdout.
+//!
+//! JSON is converted to and from TBON losslessly, using the conventions documented in
+//! [`tbon::json`].
+
+use std::fs::File;
+use std::io::{self, Read, Write};
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+use bytes::Bytes;
+use clap::{Parser, Subcommand};
+use futures::executor::block_on_stream;
+use futures::stream::{self, Stream};
+
+const CHUNK_SIZE: usize = 65_536;
+
+/// Inspect and convert TBON-encoded files
+#[derive(Parser)]
+#[command(name = "tbon", version)]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Print an indented tree of each item in a TBON file, with its byte offset
+    Dump { file: Option<PathBuf> },
+    /// Check that a TBON file is well-formed, or report the byte offset of the first error
+    Validate { file: Option<PathBuf> },
+    /// Convert a TBON file to JSON
+    ToJson { file: Option<PathBuf> },
+    /// Convert a JSON file to TBON
+    FromJson { file: Option<PathBuf> },
+    /// Count the values of each type in a TBON file, and measure its nesting depth
+    /// and escape overhead
+    Stats { file: Option<PathBuf> },
+}
+
+fn main() -> ExitCode {
+    let cli = Cli::parse();
+
+    match run(cli.command) {
+        Ok(code) => code,
+        Err(cause) => {
+            eprintln!("tbon: {}", cause);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn run(command: Command) -> Result<ExitCode, Box<dyn std::error::Error>> {
+    let mut stdout = io::stdout().lock();
+
+    match command {
+        Command::Dump { file } => {
+            let encoded = read(file)?;
+            stdout.write_all(tbon::debug::dump(&encoded).as_bytes())?;
+        }
+        Command::Validate { file } => {
+            let encoded = read(file)?;
+            if let Err(invalid) = tbon::debug::validate(&encoded) {
+                writeln!(stdout, "invalid: {}", invalid)?;
+                return Ok(ExitCode::FAILURE);
+            } else {
+                writeln!(stdout, "valid")?;
+            }
+        }
+        Command::ToJson { file } => {
+            for chunk in block_on_stream(tbon::json::to_json(chunks(file)?)) {
+                stdout.write_all(&chunk?)?;
+            }
+
+            writeln!(stdout)?;
+        }
+        Command::FromJson { file } => {
+            for chunk in block_on_stream(tbon::json::from_json(chunks(file)?)) {
+                stdout.write_all(&chunk?)?;
+            }
+        }
+        Command::Stats { file } => {
+            let encoded = read(file)?;
+            let stats = tbon::debug::stats(&encoded)?;
+
+            let overhead = if stats.bytes == 0 {
+                0.
+            } else {
+                100. * stats.escapes as f64 / stats.bytes as f64
+            };
+
+            writeln!(stdout, "bytes: {}", stats.bytes)?;
+            writeln!(stdout, "max depth: {}", stats.max_depth)?;
+            writeln!(stdout, "escapes: {} ({:.2}%)", stats.escapes, overhead)?;
+            writeln!(stdout, "lists: {}", stats.lists)?;
+            writeln!(stdout, "maps: {}", stats.maps)?;
+            writeln!(stdout, "strings: {}", stats.strings)?;
+
+            for (dtype, count) in &stats.scalars {
+                writeln!(stdout, "{}: {}", dtype, count)?;
+            }
+
+            for (dtype, count) in &stats.arrays {
+                let elements = stats.array_elements[dtype];
+                writeln!(
+                    stdout,
+                    "array of {}: {} ({} elements)",
+                    dtype, count, elements
+                )?;
+            }
+        }
+    }
+
+    stdout.flush()?;
+    Ok(ExitCode::SUCCESS)
+}
+
+fn open(file: Option<PathBuf>) -> io::Result<Box<dyn Read + Send>> {
+    match file {
+        Some(path) if path.as_os_str() != "-" => Ok(Box::new(File::open(path)?)),
+        _ => Ok(Box::new(io::stdin())),
+    }
+}
+
+fn read(file: Option<PathBuf>) -> io::Result<Vec<u8>> {
+    let mut encoded = Vec::new();
+    open(file)?.read_to_end(&mut encoded)?;
+    Ok(encoded)
+}
+
+/// Read the given file, or stdin, as a stream of chunks.
+fn chunks(
+    file: Option<PathBuf>,
+) -> io::Result<impl Stream<Item = io::Result<Bytes>> + Send + Unpin + 'static> {
+    let mut reader = open(file)?;
+
+    let chunks = std::iter::from_fn(move || {
+        let mut chunk = vec![0; CHUNK_SIZE];
+        match reader.read(&mut chunk) {
+            Ok(0) => None,
+            Ok(len) => {
+                chunk.truncate(len);
+                Some(Ok(Bytes::from(chunk)))
+            }
+            Err(cause) => Some(Err(cause)),
+        }
+    });
+
+    Ok(stream::iter(chunks))
+}
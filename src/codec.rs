@@ -0,0 +1,161 @@
+//! A [`tokio_util::codec`] to read and write a sequence of TBON-encoded values, e.g. over a
+//! long-lived network connection.
+//!
+//! Example:
+//! ```
+//! # use futures::{SinkExt, StreamExt};
+//! # use tokio_util::codec::Framed;
+//! # use tbon::codec::TbonCodec;
+//! # futures::executor::block_on(async {
+//! let (client, server) = tokio::io::duplex(64);
+//! let mut client = Framed::new(client, TbonCodec::<Vec<String>>::new());
+//! let mut server = Framed::new(server, TbonCodec::<Vec<String>>::new());
+//!
+//! client.send(vec!["hello".to_string()]).await.unwrap();
+//! let message: Option<Result<Vec<String>, tbon::de::Error>> = server.next().await;
+//! assert_eq!(message.unwrap().unwrap(), vec!["hello".to_string()]);
+//! # });
+//! ```
+
+use std::fmt;
+
+use bytes::{Bytes, BytesMut};
+use destream::{de::Error as _, en::Error as _, FromStream, IntoStream};
+use futures::{future, stream, FutureExt, TryStreamExt};
+
+use super::de::{self, DecoderOptions, Frame};
+use super::en::{self, EncoderOptions};
+
+/// A [`tokio_util::codec::Decoder`] and [`tokio_util::codec::Encoder`] of TBON-encoded values.
+///
+/// Each value is decoded once all of its bytes have been received. The bytes received so far are
+/// only scanned once, so a value which is split across many reads is not scanned again each time.
+///
+/// A value to encode must not wait on an async source, e.g. an unfinished stream of list items,
+/// since it's encoded synchronously.
+pub struct TbonCodec<T: FromStream> {
+    context: T::Context,
+    encoder_options: EncoderOptions,
+    decoder_options: DecoderOptions,
+    frame: Frame,
+}
+
+impl<T: FromStream> TbonCodec<T>
+where
+    T::Context: Default,
+{
+    /// Construct a new [`TbonCodec`].
+    pub fn new() -> Self {
+        Self::with_context(T::Context::default())
+    }
+}
+
+impl<T: FromStream> Default for TbonCodec<T>
+where
+    T::Context: Default,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: FromStream> TbonCodec<T> {
+    /// Construct a new [`TbonCodec`] which decodes each value using the given `context`.
+    pub fn with_context(context: T::Context) -> Self {
+        Self::with_options(
+            context,
+            EncoderOptions::default(),
+            DecoderOptions::default(),
+        )
+    }
+
+    /// Construct a new [`TbonCodec`] which decodes each value using the given `context`,
+    /// encoding each value with the given `encoder_options` and enforcing the limits set in the
+    /// given `decoder_options` for each value decoded.
+    pub fn with_options(
+        context: T::Context,
+        encoder_options: EncoderOptions,
+        decoder_options: DecoderOptions,
+    ) -> Self {
+        Self {
+            context,
+            encoder_options,
+            decoder_options,
+            frame: Frame::default(),
+        }
+    }
+}
+
+impl<T: FromStream> TbonCodec<T>
+where
+    T::Context: Clone,
+{
+    fn decode_value(&mut self, encoded: Bytes) -> Result<T, de::Error> {
+        let source = stream::once(future::ready(encoded));
+        let context = self.context.clone();
+
+        // the whole value has been received, so decoding it does not wait on the source
+        de::decode_with_options(context, source, self.decoder_options)
+            .now_or_never()
+            .unwrap_or_else(|| Err(de::Error::custom("decoding a TBON value did not complete")))
+    }
+}
+
+impl<T> tokio_util::codec::Decoder for TbonCodec<T>
+where
+    T: FromStream,
+    T::Context: Clone,
+{
+    type Item = T;
+    type Error = de::Error;
+
+    fn decode(&mut self, src: &mut BytesMut) -> Result<Option<T>, de::Error> {
+        match self.frame.scan(src, &self.decoder_options)? {
+            Some(len) => {
+                let encoded = src.split_to(len).freeze();
+                self.decode_value(encoded).map(Some)
+            }
+            None => Ok(None),
+        }
+    }
+
+    fn decode_eof(&mut self, src: &mut BytesMut) -> Result<Option<T>, de::Error> {
+        match self.decode(src)? {
+            Some(value) => Ok(Some(value)),
+            None if src.is_empty() => Ok(None),
+            None => {
+                // decode the incomplete value in order to report where it was cut off
+                self.frame = Frame::default();
+                let encoded = src.split().freeze();
+                self.decode_value(encoded).map(Some)
+            }
+        }
+    }
+}
+
+impl<'en, T: FromStream, I: IntoStream<'en> + 'en> tokio_util::codec::Encoder<I> for TbonCodec<T> {
+    type Error = en::Error;
+
+    fn encode(&mut self, item: I, dst: &mut BytesMut) -> Result<(), en::Error> {
+        let mut encoded = en::encode_with_options(item, self.encoder_options)?;
+
+        loop {
+            match encoded.try_next().now_or_never() {
+                Some(Ok(Some(chunk))) => dst.extend_from_slice(&chunk),
+                Some(Ok(None)) => return Ok(()),
+                Some(Err(cause)) => return Err(cause),
+                None => {
+                    return Err(en::Error::custom(
+                        "cannot encode a value which is not ready",
+                    ))
+                }
+            }
+        }
+    }
+}
+
+impl<T: FromStream> fmt::Debug for TbonCodec<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("a TBON codec")
+    }
+}
@@ -3,6 +3,7 @@ use std::fmt;
 use num_derive::{FromPrimitive, ToPrimitive};
 
 pub const ARRAY_DELIMIT: &'static [u8; 1] = &[b'='];
+pub const ARRAY_PREFIXED: &'static [u8; 1] = &[b'#'];
 pub const ESCAPE: &'static [u8; 1] = &[b'\\'];
 pub const LIST_BEGIN: &'static [u8; 1] = &[b'['];
 pub const LIST_END: &'static [u8; 1] = &[b']'];
@@ -26,11 +27,18 @@ pub enum Type {
     U16,
     U32,
     U64,
+    I128,
+    U128,
+    F16,
+    BF16,
+    Uuid,
+    VarInt,
+    VarUInt,
 }
 
-impl fmt::Display for Type {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        f.write_str(match self {
+impl Type {
+    pub fn name(&self) -> &'static str {
+        match self {
             Self::None => "none",
             Self::Bool => "boolean",
             Self::F32 => "32-bit float",
@@ -43,6 +51,49 @@ impl fmt::Display for Type {
             Self::U16 => "16-bit unsigned int",
             Self::U32 => "32-bit unsigned int",
             Self::U64 => "64-bit unsigned int",
-        })
+            Self::I128 => "128-bit int",
+            Self::U128 => "128-bit unsigned int",
+            Self::F16 => "16-bit float",
+            Self::BF16 => "16-bit brain float",
+            Self::Uuid => "UUID",
+            Self::VarInt => "variable-length int",
+            Self::VarUInt => "variable-length unsigned int",
+        }
+    }
+
+    /// Return `true` if this is a fixed-width integer type.
+    pub fn is_integer(&self) -> bool {
+        matches!(
+            self,
+            Self::I8
+                | Self::I16
+                | Self::I32
+                | Self::I64
+                | Self::I128
+                | Self::U8
+                | Self::U16
+                | Self::U32
+                | Self::U64
+                | Self::U128
+        )
+    }
+
+    /// The encoded size of a value of this type, in bytes, not including its type bit,
+    /// or zero if the size is not fixed.
+    pub fn size(&self) -> usize {
+        match self {
+            Self::None | Self::VarInt | Self::VarUInt => 0,
+            Self::Bool | Self::I8 | Self::U8 => 1,
+            Self::I16 | Self::U16 | Self::F16 | Self::BF16 => 2,
+            Self::F32 | Self::I32 | Self::U32 => 4,
+            Self::F64 | Self::I64 | Self::U64 => 8,
+            Self::I128 | Self::U128 | Self::Uuid => 16,
+        }
+    }
+}
+
+impl fmt::Display for Type {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.name())
     }
 }
@@ -0,0 +1,103 @@
+use std::collections::VecDeque;
+
+use bytes::{Buf, BufMut, Bytes, BytesMut};
+
+/// A queue of [`Bytes`] chunks which can be consumed from the front in constant time
+/// without copying.
+#[derive(Default)]
+pub struct Buffer {
+    chunks: VecDeque<Bytes>,
+    len: usize,
+}
+
+impl Buffer {
+    /// Append a chunk of [`Bytes`] to the end of this [`Buffer`].
+    pub fn push(&mut self, chunk: Bytes) {
+        if !chunk.is_empty() {
+            self.len += chunk.len();
+            self.chunks.push_back(chunk);
+        }
+    }
+
+    /// The number of bytes in this [`Buffer`].
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Return `true` if this [`Buffer`] is empty.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// The first byte in this [`Buffer`], if any.
+    pub fn first(&self) -> Option<u8> {
+        self.chunks.front().map(|chunk| chunk[0])
+    }
+
+    /// The byte at index `i` in this [`Buffer`], if any.
+    pub fn get(&self, mut i: usize) -> Option<u8> {
+        for chunk in &self.chunks {
+            if i < chunk.len() {
+                return Some(chunk[i]);
+            } else {
+                i -= chunk.len();
+            }
+        }
+
+        None
+    }
+
+    /// Iterate over the contents of this [`Buffer`], one chunk at a time.
+    pub fn chunks(&self) -> impl Iterator<Item = &[u8]> {
+        self.chunks.iter().map(|chunk| chunk.as_ref())
+    }
+}
+
+impl Buf for Buffer {
+    fn remaining(&self) -> usize {
+        self.len
+    }
+
+    fn chunk(&self) -> &[u8] {
+        self.chunks
+            .front()
+            .map(|chunk| chunk.as_ref())
+            .unwrap_or(&[])
+    }
+
+    fn advance(&mut self, mut cnt: usize) {
+        assert!(cnt <= self.len, "cannot advance past the end of a Buffer");
+        self.len -= cnt;
+
+        while cnt > 0 {
+            let front = self.chunks.front_mut().expect("chunk");
+            if cnt < front.len() {
+                front.advance(cnt);
+                break;
+            } else {
+                cnt -= front.len();
+                self.chunks.pop_front();
+            }
+        }
+    }
+
+    fn copy_to_bytes(&mut self, len: usize) -> Bytes {
+        assert!(len <= self.len, "cannot read past the end of a Buffer");
+
+        match self.chunks.front_mut() {
+            Some(front) if front.len() > len => {
+                self.len -= len;
+                front.split_to(len)
+            }
+            Some(front) if front.len() == len => {
+                self.len -= len;
+                self.chunks.pop_front().expect("chunk")
+            }
+            _ => {
+                let mut copy = BytesMut::with_capacity(len);
+                copy.put((&mut *self).take(len));
+                copy.freeze()
+            }
+        }
+    }
+}
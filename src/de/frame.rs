@@ -0,0 +1,223 @@
+use memchr::memchr2;
+use num_traits::FromPrimitive;
+
+use crate::constants::*;
+use crate::varint;
+
+use super::{DecoderOptions, Error, ErrorKind};
+
+/// A string or typed array which has begun but not yet ended
+enum Item {
+    /// A string or array which ends with the given delimiter, whose next byte is escaped if
+    /// `escaped` is `true`
+    Delimited { end: u8, escaped: bool },
+    /// A length-prefixed array of elements of the given size, at the start of a chunk
+    Prefixed { size: usize },
+}
+
+/// Finds the end of the TBON-encoded value at the front of a buffer which is received
+/// incrementally, so that the value can be decoded once it's complete.
+///
+/// Each call to [`Frame::scan`] resumes from where the last one left off, so a value which is
+/// split across many reads is only scanned once.
+#[derive(Default)]
+pub struct Frame {
+    scanned: usize,
+    open: Vec<u8>,
+    item: Option<Item>,
+}
+
+impl Frame {
+    /// Scan the given `buffer`, which begins with the bytes given to the last call, if any,
+    /// and return the encoded length of the value at its front, including its magic header,
+    /// if the value is complete.
+    ///
+    /// This only checks enough of the encoding to find the end of the value; the value must still
+    /// be decoded to check the rest. Once a complete value is found, this [`Frame`] is reset to scan
+    /// the next one.
+    pub fn scan(
+        &mut self,
+        buffer: &[u8],
+        options: &DecoderOptions,
+    ) -> Result<Option<usize>, Error> {
+        if self.scanned == 0 {
+            let header = options.magic_header.map(<[u8]>::len).unwrap_or_default();
+            if buffer.len() <= header {
+                return Ok(None);
+            }
+
+            self.scanned = header;
+        }
+
+        loop {
+            let complete = if self.item.is_some() {
+                if !self.scan_item(buffer, options.max_bytes)? {
+                    break;
+                }
+
+                self.item = None;
+                self.open.is_empty()
+            } else if let Some(first) = buffer.get(self.scanned).copied() {
+                if !self.scan_next(buffer, first)? {
+                    break;
+                }
+
+                self.item.is_none() && self.open.is_empty()
+            } else {
+                break;
+            };
+
+            if complete {
+                let len = self.scanned;
+                *self = Self::default();
+                return Ok(Some(len));
+            }
+        }
+
+        // a value which exceeds the limit would be rejected once decoded, so don't keep buffering it
+        if self.scanned > options.max_bytes {
+            let max = options.max_bytes;
+            Err(Error::limit_exceeded("stream length", max).at(self.scanned))
+        } else {
+            Ok(None)
+        }
+    }
+
+    /// Scan the beginning of the next item in the `buffer`, which begins with `first`,
+    /// or return `false` if it's not buffered yet.
+    fn scan_next(&mut self, buffer: &[u8], first: u8) -> Result<bool, Error> {
+        let offset = self.scanned;
+
+        match &[first] {
+            LIST_BEGIN => self.open.push(LIST_END[0]),
+            MAP_BEGIN => self.open.push(MAP_END[0]),
+            LIST_END | MAP_END => {
+                if self.open.pop() != Some(first) {
+                    let message = format!("unexpected delimiter {}", first as char);
+                    return Err(Error::new(ErrorKind::UnexpectedDelimiter, message).at(offset));
+                }
+            }
+            STRING_DELIMIT => {
+                self.item = Some(Item::Delimited {
+                    end: STRING_DELIMIT[0],
+                    escaped: false,
+                });
+            }
+            ARRAY_DELIMIT | ARRAY_PREFIXED => {
+                let dtype = match buffer.get(offset + 1) {
+                    Some(dtype) => *dtype,
+                    None => return Ok(false),
+                };
+
+                let size = Type::from_u8(dtype).map(|dtype| dtype.size()).unwrap_or(0);
+                if size == 0 {
+                    return Err(Error::unknown_type_bit(dtype).at(offset + 1));
+                }
+
+                self.item = Some(if first == ARRAY_PREFIXED[0] {
+                    Item::Prefixed { size }
+                } else {
+                    Item::Delimited {
+                        end: ARRAY_DELIMIT[0],
+                        escaped: false,
+                    }
+                });
+
+                self.scanned += 2;
+                return Ok(true);
+            }
+            [dtype] => {
+                let dtype = Type::from_u8(*dtype)
+                    .ok_or_else(|| Error::unknown_type_bit(*dtype).at(offset))?;
+
+                let size = match dtype {
+                    Type::VarInt | Type::VarUInt => {
+                        let encoded = &buffer[offset + 1..];
+                        match varint::len(encoded) {
+                            Some(len) => len,
+                            None if encoded.len() >= varint::MAX_LEN => {
+                                return Err(Error::varint_overflow().at(offset))
+                            }
+                            None => return Ok(false),
+                        }
+                    }
+                    dtype => dtype.size(),
+                };
+
+                if buffer.len() < offset + 1 + size {
+                    return Ok(false);
+                }
+
+                self.scanned += size;
+            }
+        }
+
+        self.scanned += 1;
+        Ok(true)
+    }
+
+    /// Scan as much of the string or typed array which has begun as is buffered,
+    /// and return `true` if it has ended.
+    fn scan_item(&mut self, buffer: &[u8], max_bytes: usize) -> Result<bool, Error> {
+        match self.item.as_mut().expect("item") {
+            Item::Delimited { end, escaped } => loop {
+                let remaining = &buffer[self.scanned..];
+
+                if *escaped {
+                    if remaining.is_empty() {
+                        return Ok(false);
+                    }
+
+                    *escaped = false;
+                    self.scanned += 1;
+                    continue;
+                }
+
+                match memchr2(*end, ESCAPE[0], remaining) {
+                    Some(i) => {
+                        self.scanned += i + 1;
+
+                        if remaining[i] == *end {
+                            return Ok(true);
+                        } else {
+                            *escaped = true;
+                        }
+                    }
+                    None => {
+                        self.scanned = buffer.len();
+                        return Ok(false);
+                    }
+                }
+            },
+            Item::Prefixed { size } => loop {
+                let offset = self.scanned;
+                let remaining = &buffer[offset..];
+
+                let varint_len = match varint::len(remaining) {
+                    Some(varint_len) => varint_len,
+                    None if remaining.len() >= varint::MAX_LEN => {
+                        return Err(Error::chunk_overflow().at(offset))
+                    }
+                    None => return Ok(false),
+                };
+
+                let len = varint::decode(&remaining[..varint_len])
+                    .and_then(|len| usize::try_from(len).ok())
+                    .and_then(|len| len.checked_mul(*size))
+                    .ok_or_else(|| Error::chunk_overflow().at(offset))?;
+
+                if offset + varint_len + len > max_bytes {
+                    return Err(Error::limit_exceeded("stream length", max_bytes).at(offset));
+                } else if remaining.len() < varint_len + len {
+                    return Ok(false);
+                }
+
+                self.scanned += varint_len + len;
+
+                if len == 0 {
+                    return Ok(true);
+                }
+            },
+        }
+    }
+}
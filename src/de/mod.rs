@@ -0,0 +1,2358 @@
+//! Decode a Rust data structure from a TBON-encoded stream.
+
+use std::fmt;
+use std::io;
+use std::marker::PhantomData;
+use std::mem;
+
+use async_recursion::async_recursion;
+use bytes::{Buf, BufMut, Bytes, BytesMut};
+use destream::{de, FromStream, Visitor};
+use futures::executor::block_on;
+use futures::stream::{Fuse, FusedStream, Stream, StreamExt, TryStreamExt};
+use futures::{future, FutureExt};
+use memchr::memchr2;
+use num_traits::{FromPrimitive, ToPrimitive};
+use uuid::Uuid;
+
+#[cfg(feature = "tokio-io")]
+use tokio::io::{AsyncRead, AsyncReadExt, BufReader};
+
+use super::constants::*;
+use super::hook;
+use super::varint;
+use super::Element;
+
+use buffer::Buffer;
+#[cfg(feature = "tokio-codec")]
+pub(crate) use frame::Frame;
+
+mod buffer;
+#[cfg(feature = "tokio-codec")]
+mod frame;
+
+const CHUNK_SIZE: usize = 4096;
+const KEY_LEN: usize = 64;
+const SNIPPET_LEN: usize = 10;
+
+/// Methods common to any decodable [`Stream`]
+#[trait_variant::make(Send)]
+pub trait Read: Send + Unpin {
+    /// Read the next chunk of [`Bytes`] in the [`Stream`], if any.
+    async fn next(&mut self) -> Option<Result<Bytes, Error>>;
+
+    /// Return `true` if there is no more content to be read from the [`Stream`].
+    fn is_terminated(&self) -> bool;
+}
+
+/// A [`Stream`] to decode
+pub struct SourceStream<S> {
+    source: Fuse<S>,
+}
+
+impl<S: Stream<Item = Result<Bytes, Error>> + Send + Unpin> Read for SourceStream<S> {
+    async fn next(&mut self) -> Option<Result<Bytes, Error>> {
+        self.source.next().await
+    }
+
+    fn is_terminated(&self) -> bool {
+        self.source.is_terminated()
+    }
+}
+
+impl<S: Stream> From<S> for SourceStream<S> {
+    fn from(source: S) -> Self {
+        Self {
+            source: source.fuse(),
+        }
+    }
+}
+
+/// A blocking reader of a decodable stream
+pub struct BlockingReader<R> {
+    reader: R,
+    terminated: bool,
+}
+
+impl<R: io::Read + Send + Unpin> Read for BlockingReader<R> {
+    async fn next(&mut self) -> Option<Result<Bytes, Error>> {
+        let mut chunk = vec![0; CHUNK_SIZE];
+        loop {
+            match self.reader.read(&mut chunk) {
+                Ok(0) => {
+                    self.terminated = true;
+                    return None;
+                }
+                Ok(size) => {
+                    chunk.truncate(size);
+                    return Some(Ok(Bytes::from(chunk)));
+                }
+                Err(cause) if cause.kind() == io::ErrorKind::Interrupted => {}
+                Err(cause) => return Some(Err(Error::io(cause))),
+            }
+        }
+    }
+
+    fn is_terminated(&self) -> bool {
+        self.terminated
+    }
+}
+
+impl<R: io::Read> From<R> for BlockingReader<R> {
+    fn from(reader: R) -> Self {
+        Self {
+            reader,
+            terminated: false,
+        }
+    }
+}
+
+/// A reader of a decodable stream from a [`futures::io::AsyncRead`] source
+#[cfg(feature = "futures-io")]
+pub struct FuturesReader<R> {
+    reader: R,
+    terminated: bool,
+}
+
+#[cfg(feature = "futures-io")]
+impl<R: futures::io::AsyncRead + Send + Unpin> Read for FuturesReader<R> {
+    async fn next(&mut self) -> Option<Result<Bytes, Error>> {
+        let mut chunk = vec![0; CHUNK_SIZE];
+        loop {
+            match futures::io::AsyncReadExt::read(&mut self.reader, &mut chunk).await {
+                Ok(0) => {
+                    self.terminated = true;
+                    return None;
+                }
+                Ok(size) => {
+                    chunk.truncate(size);
+                    return Some(Ok(Bytes::from(chunk)));
+                }
+                Err(cause) if cause.kind() == io::ErrorKind::Interrupted => {}
+                Err(cause) => return Some(Err(Error::io(cause))),
+            }
+        }
+    }
+
+    fn is_terminated(&self) -> bool {
+        self.terminated
+    }
+}
+
+#[cfg(feature = "futures-io")]
+impl<R: futures::io::AsyncRead> From<R> for FuturesReader<R> {
+    fn from(reader: R) -> Self {
+        Self {
+            reader,
+            terminated: false,
+        }
+    }
+}
+
+/// A buffered reader of a decodable stream
+#[cfg(feature = "tokio-io")]
+pub struct SourceReader<R: AsyncRead> {
+    reader: BufReader<R>,
+    terminated: bool,
+}
+
+#[cfg(feature = "tokio-io")]
+impl<R: AsyncRead + Send + Unpin> Read for SourceReader<R> {
+    async fn next(&mut self) -> Option<Result<Bytes, Error>> {
+        let mut chunk = Vec::new();
+        match self.reader.read_buf(&mut chunk).await {
+            Ok(0) => {
+                self.terminated = true;
+                Some(Ok(Bytes::from(chunk)))
+            }
+            Ok(size) => {
+                debug_assert_eq!(chunk.len(), size);
+                Some(Ok(Bytes::from(chunk)))
+            }
+            Err(cause) => Some(Err(Error::io(cause))),
+        }
+    }
+
+    fn is_terminated(&self) -> bool {
+        self.terminated
+    }
+}
+
+#[cfg(feature = "tokio-io")]
+impl<R: AsyncRead> From<R> for SourceReader<R> {
+    fn from(reader: R) -> Self {
+        Self {
+            reader: BufReader::new(reader),
+            terminated: false,
+        }
+    }
+}
+
+/// Limits to enforce when decoding a TBON stream, e.g. one received from an untrusted source.
+///
+/// By default no limits are enforced.
+#[derive(Clone, Copy, Debug)]
+pub struct DecoderOptions {
+    max_depth: usize,
+    max_string_len: usize,
+    max_array_len: usize,
+    max_bytes: usize,
+    max_map_entries: usize,
+    magic_header: Option<&'static [u8]>,
+}
+
+impl Default for DecoderOptions {
+    fn default() -> Self {
+        Self {
+            max_depth: usize::MAX,
+            max_string_len: usize::MAX,
+            max_array_len: usize::MAX,
+            max_bytes: usize::MAX,
+            max_map_entries: usize::MAX,
+            magic_header: None,
+        }
+    }
+}
+
+impl DecoderOptions {
+    /// Construct a new set of [`DecoderOptions`] which does not enforce any limits.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the maximum nesting depth of lists and maps.
+    pub fn max_depth(mut self, max_depth: usize) -> Self {
+        self.max_depth = max_depth;
+        self
+    }
+
+    /// Set the maximum length of an encoded string, in bytes.
+    pub fn max_string_len(mut self, max_string_len: usize) -> Self {
+        self.max_string_len = max_string_len;
+        self
+    }
+
+    /// Set the maximum number of elements in a list or typed array.
+    pub fn max_array_len(mut self, max_array_len: usize) -> Self {
+        self.max_array_len = max_array_len;
+        self
+    }
+
+    /// Set the maximum total number of bytes to read from the source.
+    pub fn max_bytes(mut self, max_bytes: usize) -> Self {
+        self.max_bytes = max_bytes;
+        self
+    }
+
+    /// Set the maximum number of entries in a map.
+    pub fn max_map_entries(mut self, max_map_entries: usize) -> Self {
+        self.max_map_entries = max_map_entries;
+        self
+    }
+
+    /// Require the stream to begin with the given `magic_header`, as written by an encoder
+    /// configured with [`crate::en::EncoderOptions::magic_header`].
+    ///
+    /// The header is checked and skipped by the decoding functions in this module, e.g.
+    /// [`decode_with_options`], not by a [`Decoder`] constructed directly.
+    pub fn magic_header(mut self, magic_header: &'static [u8]) -> Self {
+        self.magic_header = Some(magic_header);
+        self
+    }
+}
+
+/// The category of an [`Error`] encountered while decoding a TBON stream.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum ErrorKind {
+    /// The stream ended before the value being decoded was complete.
+    UnexpectedEnd,
+    /// The stream contains a different type than the one expected.
+    InvalidType,
+    /// A value in the stream has an unexpected length.
+    InvalidLength,
+    /// A string in the stream is not valid UTF-8.
+    InvalidUtf8,
+    /// The stream contains a type bit which is not part of the TBON format.
+    UnknownTypeBit,
+    /// The stream contains a delimiter other than the one expected.
+    UnexpectedDelimiter,
+    /// The stream exceeds one of the limits set in the [`DecoderOptions`].
+    LimitExceeded,
+    /// The stream does not begin with the magic header set in the [`DecoderOptions`].
+    MissingHeader,
+    /// The source of the stream returned an error.
+    Io,
+    /// Any other error, e.g. one raised by a [`FromStream`] implementation.
+    Custom,
+}
+
+/// An error encountered while decoding a TBON stream.
+pub struct Error {
+    kind: ErrorKind,
+    message: String,
+    offset: Option<usize>,
+    path: Option<String>,
+}
+
+impl Error {
+    pub(crate) fn new<M: fmt::Display>(kind: ErrorKind, message: M) -> Self {
+        Self {
+            kind,
+            message: message.to_string(),
+            offset: None,
+            path: None,
+        }
+    }
+
+    fn invalid_utf8<I: fmt::Display>(info: I) -> Self {
+        Self::new(ErrorKind::InvalidUtf8, format!("invalid UTF-8: {}", info))
+    }
+
+    fn io<I: fmt::Display>(cause: I) -> Self {
+        Self::new(ErrorKind::Io, format!("io error: {}", cause))
+    }
+
+    fn unexpected_end() -> Self {
+        Self::new(ErrorKind::UnexpectedEnd, "unexpected end of stream")
+    }
+
+    fn unknown_type_bit(bit: u8) -> Self {
+        Self::new(
+            ErrorKind::UnknownTypeBit,
+            format!("invalid type bit: {}", bit),
+        )
+    }
+
+    fn type_mismatch(bit: u8, expected: Type) -> Self {
+        match Type::from_u8(bit) {
+            Some(actual) => de::Error::invalid_type(actual, expected),
+            None => Self::unknown_type_bit(bit),
+        }
+    }
+
+    fn chunk_overflow() -> Self {
+        Self::new(ErrorKind::InvalidLength, "array chunk length overflow")
+    }
+
+    fn varint_overflow() -> Self {
+        Self::new(ErrorKind::InvalidLength, "variable-length integer overflow")
+    }
+
+    fn missing_header(header: &[u8]) -> Self {
+        let message = format!("missing magic header {:?}", Bytes::copy_from_slice(header));
+        Self::new(ErrorKind::MissingHeader, message)
+    }
+
+    fn limit_exceeded(limit: &str, max: usize) -> Self {
+        let message = format!("limit exceeded: {} is greater than {}", limit, max);
+        Self::new(ErrorKind::LimitExceeded, message)
+    }
+
+    /// Set the offset of this error, unless it's already set.
+    fn at(mut self, offset: usize) -> Self {
+        if self.offset.is_none() {
+            self.offset = Some(offset);
+        }
+
+        self
+    }
+
+    /// Set the path of this error within the value being decoded, unless it's already set.
+    fn within(mut self, path: &[PathSegment]) -> Self {
+        if self.path.is_none() && !path.is_empty() {
+            let mut rendered = String::new();
+            for (i, segment) in path.iter().enumerate() {
+                match segment {
+                    PathSegment::Index(index) => rendered.push_str(&format!("[{}]", index)),
+                    PathSegment::Key(key) if i == 0 => {
+                        rendered.push_str(&format!("{{{}}}", render_key(key)))
+                    }
+                    PathSegment::Key(key) => {
+                        rendered.push_str(&format!(".{{{}}}", render_key(key)))
+                    }
+                }
+            }
+
+            self.path = Some(rendered);
+        }
+
+        self
+    }
+
+    /// The category of this error.
+    pub fn kind(&self) -> ErrorKind {
+        self.kind
+    }
+
+    /// The offset in the source stream, in bytes, where this error was encountered, if known.
+    pub fn offset(&self) -> Option<usize> {
+        self.offset
+    }
+
+    /// The path of the value where this error was encountered, e.g. `[2].{"weights"}[17]`,
+    /// if it was encountered inside a list or map.
+    pub fn path(&self) -> Option<&str> {
+        self.path.as_deref()
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl From<io::Error> for Error {
+    fn from(cause: io::Error) -> Self {
+        Self::io(cause)
+    }
+}
+
+impl de::Error for Error {
+    fn custom<T: fmt::Display>(msg: T) -> Self {
+        Self::new(ErrorKind::Custom, msg)
+    }
+
+    fn invalid_type<U: fmt::Display, E: fmt::Display>(unexp: U, exp: E) -> Self {
+        let message = format!("invalid type: {}, expected {}", unexp, exp);
+        Self::new(ErrorKind::InvalidType, message)
+    }
+
+    fn invalid_length<E: fmt::Display>(len: usize, exp: E) -> Self {
+        let message = format!("invalid length: {}, expected {}", len, exp);
+        Self::new(ErrorKind::InvalidLength, message)
+    }
+}
+
+impl fmt::Debug for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Display::fmt(self, f)
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Display::fmt(&self.message, f)?;
+
+        if let Some(path) = &self.path {
+            write!(f, " at {}", path)?;
+        }
+
+        if let Some(offset) = self.offset {
+            write!(f, " (offset {})", offset)?;
+        }
+
+        Ok(())
+    }
+}
+
+enum PathSegment {
+    Index(usize),
+    Key(Vec<u8>),
+}
+
+/// Render the (possibly truncated) encoding of a map key for display in an error message.
+fn render_key(encoded: &[u8]) -> String {
+    fn render<N: Element + fmt::Display>(encoded: &[u8]) -> String {
+        if encoded.len() > N::SIZE {
+            N::from_bytes(&encoded[1..N::SIZE + 1]).to_string()
+        } else {
+            "...".to_string()
+        }
+    }
+
+    fn render_varint(encoded: &[u8], signed: bool) -> String {
+        let encoded = &encoded[1..];
+        match varint::len(encoded).and_then(|len| varint::decode(&encoded[..len])) {
+            Some(n) if signed => varint::unzigzag(n).to_string(),
+            Some(n) => n.to_string(),
+            None => "...".to_string(),
+        }
+    }
+
+    match encoded.first().copied() {
+        Some(b'"') => {
+            let mut key = Vec::with_capacity(encoded.len());
+            let mut escape = false;
+            for byte in &encoded[1..] {
+                if escape {
+                    key.push(*byte);
+                    escape = false;
+                } else if *byte == ESCAPE[0] {
+                    escape = true;
+                } else if *byte == STRING_DELIMIT[0] {
+                    return format!("{:?}", String::from_utf8_lossy(&key));
+                } else {
+                    key.push(*byte);
+                }
+            }
+
+            format!("{:?}...", String::from_utf8_lossy(&key))
+        }
+        Some(dtype) => match Type::from_u8(dtype) {
+            Some(Type::None) => "none".to_string(),
+            Some(Type::Bool) => render::<bool>(encoded),
+            Some(Type::F32) => render::<f32>(encoded),
+            Some(Type::F64) => render::<f64>(encoded),
+            Some(Type::I8) => render::<i8>(encoded),
+            Some(Type::I16) => render::<i16>(encoded),
+            Some(Type::I32) => render::<i32>(encoded),
+            Some(Type::I64) => render::<i64>(encoded),
+            Some(Type::U8) => render::<u8>(encoded),
+            Some(Type::U16) => render::<u16>(encoded),
+            Some(Type::U32) => render::<u32>(encoded),
+            Some(Type::U64) => render::<u64>(encoded),
+            Some(Type::I128) => render::<i128>(encoded),
+            Some(Type::U128) => render::<u128>(encoded),
+            Some(Type::Uuid) => render::<Uuid>(encoded),
+            Some(Type::VarInt) => render_varint(encoded, true),
+            Some(Type::VarUInt) => render_varint(encoded, false),
+            #[cfg(feature = "half")]
+            Some(Type::F16) => render::<half::f16>(encoded),
+            #[cfg(feature = "half")]
+            Some(Type::BF16) => render::<half::bf16>(encoded),
+            #[cfg(not(feature = "half"))]
+            Some(Type::F16 | Type::BF16) => "...".to_string(),
+            None => "...".to_string(),
+        },
+        None => "...".to_string(),
+    }
+}
+
+struct ArrayAccess<'a, S, T> {
+    decoder: &'a mut Decoder<S>,
+    dtype: PhantomData<T>,
+    len: usize,
+    done: bool,
+    prefixed: bool,
+    remaining: usize,
+}
+
+impl<'a, S: Read + 'a, T: Element> ArrayAccess<'a, S, T> {
+    async fn new(decoder: &'a mut Decoder<S>) -> Result<ArrayAccess<'a, S, T>, Error> {
+        let prefixed = decoder.begin_array(T::dtype()).await?;
+
+        let mut access = ArrayAccess {
+            decoder,
+            dtype: PhantomData,
+            len: 0,
+            done: false,
+            prefixed,
+            remaining: 0,
+        };
+
+        if prefixed {
+            access.next_chunk().await?;
+        } else {
+            access.done = access.decoder.maybe_delimiter(ARRAY_DELIMIT).await?;
+        }
+
+        Ok(access)
+    }
+
+    /// Read the length of the next chunk of a length-prefixed array, which is zero at the end.
+    async fn next_chunk(&mut self) -> Result<(), Error> {
+        let offset = self.decoder.offset();
+        let len = self.decoder.parse_len().await?;
+
+        let max = self.decoder.options.max_array_len;
+        if self.len.saturating_add(len) > max {
+            return Err(Error::limit_exceeded("array length", max).at(offset));
+        }
+
+        self.remaining = len;
+        self.done = len == 0;
+        Ok(())
+    }
+
+    /// Parse up to `buffer.len()` elements of the current chunk of a length-prefixed array.
+    async fn read_chunk(&mut self, buffer: &mut [T]) -> Result<usize, Error> {
+        if buffer.is_empty() {
+            return Ok(0);
+        }
+
+        let size = T::SIZE;
+        let offset = self.decoder.offset();
+
+        while self.decoder.buffer.len() < size && !self.decoder.source.is_terminated() {
+            self.decoder.buffer().await?;
+        }
+
+        if self.decoder.buffer.len() < size {
+            return Err(Error::unexpected_end().at(self.decoder.read));
+        }
+
+        let chunk = self.decoder.buffer.chunk();
+        let elements = if chunk.len() >= size {
+            // parse elements directly from the source chunk, since their length is known
+            let elements = Ord::min(Ord::min(self.remaining, buffer.len()), chunk.len() / size);
+            for (i, bytes) in chunk[..elements * size].chunks(size).enumerate() {
+                buffer[i] = T::parse(bytes).map_err(|e: Error| e.at(offset))?;
+            }
+
+            self.decoder.advance(elements * size);
+            elements
+        } else {
+            // this element spans more than one chunk of the source
+            let bytes = self.decoder.consume(size);
+            buffer[0] = T::parse(&bytes).map_err(|e: Error| e.at(offset))?;
+            1
+        };
+
+        self.remaining -= elements;
+        if self.remaining == 0 {
+            self.next_chunk().await?;
+        }
+
+        Ok(elements)
+    }
+}
+
+impl<'a, S: Read + 'a, T: Element + Send> de::ArrayAccess<T> for ArrayAccess<'a, S, T> {
+    type Error = Error;
+
+    async fn buffer(&mut self, buffer: &mut [T]) -> Result<usize, Self::Error> {
+        if self.done {
+            return Ok(0);
+        } else if self.prefixed {
+            let elements = self.read_chunk(buffer).await?;
+            self.len += elements;
+            return Ok(elements);
+        }
+
+        let size = T::SIZE;
+        let offset = self.decoder.offset();
+
+        while self.decoder.buffer.is_empty() && !self.decoder.source.is_terminated() {
+            self.decoder.buffer().await?;
+        }
+
+        let (literal, _) = self.decoder.scan_literal(ARRAY_DELIMIT[0]);
+        let literal = (Ord::min(literal, buffer.len() * size) / size) * size;
+
+        let elements = if literal > 0 {
+            // parse elements directly from the source chunk, since there is nothing to unescape
+            let chunk = &self.decoder.buffer.chunk()[..literal];
+            for (i, bytes) in chunk.chunks(size).enumerate() {
+                buffer[i] = T::parse(bytes).map_err(|e: Error| e.at(offset))?;
+            }
+
+            self.decoder.advance(literal);
+            self.done = self.decoder.maybe_delimiter(ARRAY_DELIMIT).await?;
+
+            literal / size
+        } else {
+            let mut escaped = BytesMut::with_capacity(buffer.len() * size);
+            let done = self
+                .decoder
+                .unescape(ARRAY_DELIMIT[0], buffer.len() * size, Some(&mut escaped))
+                .await?;
+
+            let mut elements = 0;
+
+            for bytes in escaped.chunks(size) {
+                buffer[elements] = T::parse(bytes).map_err(|e: Error| e.at(offset))?;
+                elements += 1;
+            }
+
+            if done {
+                self.done = true;
+                // process the end delimiter
+                self.decoder.advance(1);
+            }
+
+            elements
+        };
+
+        self.len += elements;
+        if self.len > self.decoder.options.max_array_len {
+            let max = self.decoder.options.max_array_len;
+            return Err(Error::limit_exceeded("array length", max).at(offset));
+        }
+
+        Ok(elements)
+    }
+}
+
+/// Access to an array of type `E`, whose elements are converted to another type as they're read,
+/// since [`Visitor`] has no methods for 128-bit integers or 16-bit floats.
+struct CastArrayAccess<'a, S, E> {
+    access: ArrayAccess<'a, S, E>,
+    elements: Vec<E>,
+}
+
+impl<'a, S, E> CastArrayAccess<'a, S, E> {
+    fn new(access: ArrayAccess<'a, S, E>) -> Self {
+        Self {
+            access,
+            elements: Vec::new(),
+        }
+    }
+}
+
+impl<'a, S, E, T> de::ArrayAccess<T> for CastArrayAccess<'a, S, E>
+where
+    S: Read + 'a,
+    E: Element + Copy + Default + fmt::Display + Send,
+    T: TryFrom<E> + Send,
+{
+    type Error = Error;
+
+    async fn buffer(&mut self, buffer: &mut [T]) -> Result<usize, Self::Error> {
+        let offset = self.access.decoder.offset();
+
+        self.elements.resize(buffer.len(), E::default());
+        let len = de::ArrayAccess::buffer(&mut self.access, &mut self.elements).await?;
+
+        for (cast, element) in buffer.iter_mut().zip(&self.elements[..len]) {
+            *cast = cast_element(*element).map_err(|e| e.at(offset))?;
+        }
+
+        Ok(len)
+    }
+}
+
+/// Convert `value` to the type `T`, or return an error if it's out of range.
+fn cast_element<E: fmt::Display + Copy, T: TryFrom<E>>(value: E) -> Result<T, Error> {
+    T::try_from(value).map_err(|_| {
+        let expected = format!("a value in the range of {}", std::any::type_name::<T>());
+        de::Error::invalid_value(value, expected)
+    })
+}
+
+/// Access to a `u8` array which has already been read into a single buffer.
+///
+/// [`de::ArrayAccess`] can only fill a buffer provided by the [`Visitor`], so the visitor still
+/// copies the bytes, but they don't need to be scanned for escapes or delimiters first.
+struct ByteBufferAccess {
+    bytes: Bytes,
+}
+
+impl de::ArrayAccess<u8> for ByteBufferAccess {
+    type Error = Error;
+
+    async fn buffer(&mut self, buffer: &mut [u8]) -> Result<usize, Self::Error> {
+        let len = Ord::min(buffer.len(), self.bytes.len());
+        buffer[..len].copy_from_slice(&self.bytes.split_to(len));
+        Ok(len)
+    }
+}
+
+/// Access to the bytes of a [`Uuid`], as if it were a `u8` array
+struct UuidAccess {
+    uuid: Option<Uuid>,
+}
+
+impl de::ArrayAccess<u8> for UuidAccess {
+    type Error = Error;
+
+    async fn buffer(&mut self, buffer: &mut [u8]) -> Result<usize, Self::Error> {
+        let Some(uuid) = self.uuid.take() else {
+            return Ok(0);
+        };
+
+        if buffer.len() < Uuid::SIZE {
+            let message = format!("a buffer of length {} to decode a UUID", Uuid::SIZE);
+            Err(de::Error::invalid_length(buffer.len(), message))
+        } else {
+            buffer[..Uuid::SIZE].copy_from_slice(uuid.as_bytes());
+            Ok(Uuid::SIZE)
+        }
+    }
+}
+
+struct MapAccess<'a, S> {
+    decoder: &'a mut Decoder<S>,
+    size_hint: Option<usize>,
+    key: Vec<u8>,
+    len: usize,
+    done: bool,
+}
+
+impl<'a, S: Read + 'a> MapAccess<'a, S> {
+    async fn new(
+        decoder: &'a mut Decoder<S>,
+        size_hint: Option<usize>,
+    ) -> Result<MapAccess<'a, S>, Error> {
+        decoder.expect_delimiter(MAP_BEGIN).await?;
+
+        let done = decoder.maybe_delimiter(MAP_END).await?;
+
+        Ok(MapAccess {
+            decoder,
+            size_hint,
+            key: Vec::new(),
+            len: 0,
+            done,
+        })
+    }
+}
+
+impl<'a, S: Read + 'a> de::MapAccess for MapAccess<'a, S> {
+    type Error = Error;
+
+    async fn next_key<K: FromStream>(&mut self, context: K::Context) -> Result<Option<K>, Error> {
+        if self.done {
+            return Ok(None);
+        }
+
+        let offset = self.decoder.offset();
+
+        self.len += 1;
+        if self.len > self.decoder.options.max_map_entries {
+            let max = self.decoder.options.max_map_entries;
+            return Err(Error::limit_exceeded("map size", max).at(offset));
+        }
+
+        let outer = self.decoder.record.replace(Vec::new());
+        let key = K::from_stream(context, self.decoder).await;
+        self.key = std::mem::replace(&mut self.decoder.record, outer).unwrap_or_default();
+
+        let key = key.map_err(|e| e.at(offset).within(&self.decoder.path))?;
+
+        Ok(Some(key))
+    }
+
+    async fn next_value<V: FromStream>(&mut self, context: V::Context) -> Result<V, Error> {
+        if self.done {
+            return Err(de::Error::custom(
+                "called MapAccess::next_value but the map has already ended",
+            ));
+        }
+
+        let offset = self.decoder.offset();
+        let key = std::mem::take(&mut self.key);
+
+        self.decoder.path.push(PathSegment::Key(key));
+        let value = V::from_stream(context, self.decoder)
+            .await
+            .map_err(|e| e.at(offset).within(&self.decoder.path));
+
+        self.decoder.path.pop();
+        let value = value?;
+
+        if self.decoder.maybe_delimiter(MAP_END).await? {
+            self.done = true;
+        }
+
+        Ok(value)
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        self.size_hint
+    }
+}
+
+struct SeqAccess<'a, S> {
+    decoder: &'a mut Decoder<S>,
+    size_hint: Option<usize>,
+    len: usize,
+    done: bool,
+}
+
+impl<'a, S: Read + 'a> SeqAccess<'a, S> {
+    async fn new(
+        decoder: &'a mut Decoder<S>,
+        size_hint: Option<usize>,
+    ) -> Result<SeqAccess<'a, S>, Error> {
+        decoder.expect_delimiter(LIST_BEGIN).await?;
+
+        let done = decoder.maybe_delimiter(LIST_END).await?;
+
+        Ok(SeqAccess {
+            decoder,
+            size_hint,
+            len: 0,
+            done,
+        })
+    }
+}
+
+impl<'a, S: Read + 'a> de::SeqAccess for SeqAccess<'a, S> {
+    type Error = Error;
+
+    async fn next_element<T: FromStream>(
+        &mut self,
+        context: T::Context,
+    ) -> Result<Option<T>, Self::Error> {
+        if self.done {
+            return Ok(None);
+        }
+
+        let offset = self.decoder.offset();
+
+        self.len += 1;
+        if self.len > self.decoder.options.max_array_len {
+            let max = self.decoder.options.max_array_len;
+            return Err(Error::limit_exceeded("list length", max).at(offset));
+        }
+
+        self.decoder.path.push(PathSegment::Index(self.len - 1));
+        let value = T::from_stream(context, self.decoder)
+            .await
+            .map_err(|e| e.at(offset).within(&self.decoder.path));
+
+        self.decoder.path.pop();
+        let value = value?;
+
+        if self.decoder.maybe_delimiter(LIST_END).await? {
+            self.done = true;
+        }
+
+        Ok(Some(value))
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        self.size_hint
+    }
+}
+
+/// A TBON value of a native type which [`Visitor`] has no method for
+pub(crate) enum Native {
+    I128(i128),
+    U128(u128),
+    ArrayI128(Vec<i128>),
+    ArrayU128(Vec<u128>),
+    Uuid(Uuid),
+    #[cfg(feature = "half")]
+    F16(half::f16),
+    #[cfg(feature = "half")]
+    BF16(half::bf16),
+    #[cfg(feature = "half")]
+    ArrayF16(Vec<half::f16>),
+    #[cfg(feature = "half")]
+    ArrayBF16(Vec<half::bf16>),
+}
+
+/// A [`Visitor`] in this crate which can represent a [`Native`] value losslessly
+pub(crate) trait VisitNative: Visitor + 'static {
+    /// Visit a [`Native`] value.
+    fn visit_native(self, native: Native) -> Self::Value;
+}
+
+/// Return `true` if `V` is a [`VisitNative`] visitor.
+fn visits_native<V: Visitor>() -> bool {
+    #[cfg(feature = "json")]
+    if hook::is::<crate::json::ToJsonVisitor, V>() {
+        return true;
+    }
+
+    hook::is::<crate::value::ValueVisitor, V>()
+}
+
+/// Visit a [`Native`] value with a `visitor` for which [`visits_native`] is `true`.
+fn visit_native<V: Visitor>(visitor: V, native: Native) -> V::Value {
+    fn visit_as<T: VisitNative, V: Visitor>(visitor: V, native: Native) -> V::Value {
+        // SAFETY: V is a T, which has no lifetime parameters, so V::Value is T::Value
+        unsafe {
+            let visitor: T = hook::cast(visitor);
+            hook::cast(visitor.visit_native(native))
+        }
+    }
+
+    #[cfg(feature = "json")]
+    if hook::is::<crate::json::ToJsonVisitor, V>() {
+        return visit_as::<crate::json::ToJsonVisitor, V>(visitor, native);
+    }
+
+    assert!(hook::is::<crate::value::ValueVisitor, V>());
+    visit_as::<crate::value::ValueVisitor, V>(visitor, native)
+}
+
+/// A structure that decodes Rust values from a TBON stream.
+pub struct Decoder<R> {
+    source: R,
+    buffer: Buffer,
+    options: DecoderOptions,
+    depth: usize,
+    read: usize,
+    path: Vec<PathSegment>,
+    record: Option<Vec<u8>>,
+}
+
+impl<R> Decoder<R> {
+    pub(crate) fn new(source: R, options: DecoderOptions) -> Self {
+        Self {
+            source,
+            buffer: Buffer::default(),
+            options,
+            depth: 0,
+            read: 0,
+            path: Vec::new(),
+            record: None,
+        }
+    }
+
+    fn descend(&mut self) -> Result<(), Error> {
+        if self.depth < self.options.max_depth {
+            self.depth += 1;
+            Ok(())
+        } else {
+            let max = self.options.max_depth;
+            Err(Error::limit_exceeded("nesting depth", max).at(self.offset()))
+        }
+    }
+
+    fn ascend(&mut self) {
+        self.depth -= 1;
+    }
+
+    /// Record up to [`KEY_LEN`] of the next `n` bytes of the buffer, if a map key is being read.
+    fn record(&mut self, n: usize) {
+        if let Some(record) = &mut self.record {
+            let mut len = Ord::min(n, KEY_LEN.saturating_sub(record.len()));
+
+            for chunk in self.buffer.chunks() {
+                if len == 0 {
+                    break;
+                }
+
+                let chunk = &chunk[..Ord::min(len, chunk.len())];
+                record.extend_from_slice(chunk);
+                len -= chunk.len();
+            }
+        }
+    }
+
+    /// Discard the next `n` bytes of the buffer.
+    fn advance(&mut self, n: usize) {
+        self.record(n);
+        self.buffer.advance(n);
+    }
+
+    /// Consume the next `n` bytes of the buffer.
+    fn consume(&mut self, n: usize) -> Bytes {
+        self.record(n);
+        self.buffer.copy_to_bytes(n)
+    }
+
+    /// The number of bytes of the source stream consumed so far.
+    fn offset(&self) -> usize {
+        self.read - self.buffer.len()
+    }
+
+    fn contents(&self, max_len: usize) -> String {
+        let len = Ord::min(self.buffer.len(), max_len);
+        let mut chunks: Vec<String> = Vec::with_capacity(len);
+        let mut chunk = Vec::with_capacity(len);
+        let mut is_ascii = false;
+        for c in self.buffer.chunks().flatten().take(len) {
+            if is_ascii != c.is_ascii() {
+                chunks.push(chunk.iter().collect());
+                chunk.clear();
+                is_ascii = c.is_ascii();
+            }
+
+            if c.is_ascii() {
+                chunk.push(*c as char);
+            } else {
+                chunk.extend(format!(" {} ", c).as_bytes().iter().map(|c| *c as char))
+            }
+        }
+
+        if !chunk.is_empty() {
+            chunks.push(chunk.into_iter().collect());
+        }
+
+        chunks.join("")
+    }
+}
+
+#[cfg(feature = "tokio-io")]
+impl<A: AsyncRead> Decoder<A>
+where
+    SourceReader<A>: Read,
+{
+    /// Create a new [`Decoder`] from an [`AsyncRead`] source.
+    pub fn from_reader(reader: A) -> Decoder<SourceReader<A>> {
+        Self::from_reader_with_options(reader, DecoderOptions::default())
+    }
+
+    /// Create a new [`Decoder`] from an [`AsyncRead`] source which enforces the given `options`.
+    pub fn from_reader_with_options(
+        reader: A,
+        options: DecoderOptions,
+    ) -> Decoder<SourceReader<A>> {
+        Decoder::new(SourceReader::from(reader), options)
+    }
+}
+
+impl<S: Stream> Decoder<SourceStream<S>>
+where
+    SourceStream<S>: Read,
+{
+    /// Create a new [`Decoder`] from a source [`Stream`].
+    pub fn from_stream(stream: S) -> Decoder<SourceStream<S>> {
+        Self::from_stream_with_options(stream, DecoderOptions::default())
+    }
+
+    /// Create a new [`Decoder`] from a source [`Stream`] which enforces the given `options`.
+    pub fn from_stream_with_options(
+        stream: S,
+        options: DecoderOptions,
+    ) -> Decoder<SourceStream<S>> {
+        Decoder::new(SourceStream::from(stream), options)
+    }
+}
+
+#[cfg(feature = "futures-io")]
+impl<R: futures::io::AsyncRead + Send + Unpin> Decoder<FuturesReader<R>> {
+    /// Create a new [`Decoder`] from a [`futures::io::AsyncRead`] source.
+    pub fn from_futures_reader(reader: R) -> Self {
+        Self::from_futures_reader_with_options(reader, DecoderOptions::default())
+    }
+
+    /// Create a new [`Decoder`] from a [`futures::io::AsyncRead`] source
+    /// which enforces the given `options`.
+    pub fn from_futures_reader_with_options(reader: R, options: DecoderOptions) -> Self {
+        Decoder::new(FuturesReader::from(reader), options)
+    }
+}
+
+impl<R: io::Read + Send + Unpin> Decoder<BlockingReader<R>> {
+    /// Create a new [`Decoder`] from a blocking [`io::Read`] source.
+    pub fn from_blocking_reader(reader: R) -> Self {
+        Self::from_blocking_reader_with_options(reader, DecoderOptions::default())
+    }
+
+    /// Create a new [`Decoder`] from a blocking [`io::Read`] source
+    /// which enforces the given `options`.
+    pub fn from_blocking_reader_with_options(reader: R, options: DecoderOptions) -> Self {
+        Decoder::new(BlockingReader::from(reader), options)
+    }
+}
+
+impl<R: Read> Decoder<R> {
+    /// Decode a `u8` array as [`Bytes`].
+    ///
+    /// If the array contains no escaped bytes (or, if it's length-prefixed, consists of a single
+    /// chunk) and lies within a single chunk of the source, the result is a slice of that chunk
+    /// rather than a copy.
+    pub async fn decode_byte_buffer(&mut self) -> Result<Bytes, Error> {
+        let max = self.options.max_array_len;
+
+        if self.begin_array(Type::U8).await? {
+            self.buffer_chunks(max).await
+        } else {
+            self.buffer_escaped(ARRAY_DELIMIT[0], max, "array length")
+                .await
+        }
+    }
+
+    /// If the next value is a `u8` array with no escaped bytes which lies entirely within
+    /// the first chunk of the buffer, consume it and return it as a slice of that chunk.
+    async fn literal_byte_buffer(&mut self) -> Result<Option<Bytes>, Error> {
+        while self.buffer.len() < 2 && !self.source.is_terminated() {
+            self.buffer().await?;
+        }
+
+        let chunk = self.buffer.chunk();
+        if chunk.len() < 2 || chunk[0] != ARRAY_DELIMIT[0] || Some(chunk[1]) != Type::U8.to_u8() {
+            return Ok(None);
+        }
+
+        let len = match memchr2(ARRAY_DELIMIT[0], ESCAPE[0], &chunk[2..]) {
+            Some(len) if chunk[2 + len] == ARRAY_DELIMIT[0] => len,
+            _ => return Ok(None),
+        };
+
+        if len > self.options.max_array_len {
+            return Ok(None);
+        }
+
+        self.advance(2); // process the start delimiter and type bit
+        let bytes = self.consume(len);
+        self.advance(1); // process the end delimiter
+        Ok(Some(bytes))
+    }
+
+    /// Decode a 128-bit signed integer.
+    ///
+    /// [`Visitor`] has no methods for 128-bit integers, so a value which does not fit in 64 bits
+    /// can only be decoded by calling this [`Decoder`] directly, or as a [`crate::Value`].
+    pub async fn decode_i128(&mut self) -> Result<i128, Error> {
+        self.parse_element().await
+    }
+
+    /// Decode a 128-bit unsigned integer. See [`Decoder::decode_i128`].
+    pub async fn decode_u128(&mut self) -> Result<u128, Error> {
+        self.parse_element().await
+    }
+
+    /// Decode a 16-bit float.
+    ///
+    /// [`Visitor`] has no methods for 16-bit floats, so a [`de::Decoder::decode_f32`] or
+    /// [`de::Decoder::decode_any`] call widens an `f16` or `bf16` value to an `f32`, unless it's
+    /// decoding a [`crate::Value`].
+    #[cfg(feature = "half")]
+    pub async fn decode_f16(&mut self) -> Result<half::f16, Error> {
+        self.parse_element().await
+    }
+
+    /// Decode a 16-bit "brain" float. See [`Decoder::decode_f16`].
+    #[cfg(feature = "half")]
+    pub async fn decode_bf16(&mut self) -> Result<half::bf16, Error> {
+        self.parse_element().await
+    }
+
+    /// Decode an array of 16-bit floats, without widening its elements.
+    ///
+    /// A [`de::Decoder::decode_array_f32`] or [`de::Decoder::decode_any`] call widens each
+    /// element of an `f16` or `bf16` array to an `f32`, unless it's decoding a [`crate::Value`].
+    #[cfg(feature = "half")]
+    pub async fn decode_array_f16(
+        &mut self,
+    ) -> Result<impl de::ArrayAccess<half::f16, Error = Error> + '_, Error> {
+        ArrayAccess::new(self).await
+    }
+
+    /// Decode an array of 16-bit "brain" floats. See [`Decoder::decode_array_f16`].
+    #[cfg(feature = "half")]
+    pub async fn decode_array_bf16(
+        &mut self,
+    ) -> Result<impl de::ArrayAccess<half::bf16, Error = Error> + '_, Error> {
+        ArrayAccess::new(self).await
+    }
+
+    async fn buffer(&mut self) -> Result<(), Error> {
+        if let Some(data) = self.source.next().await {
+            let data = data.map_err(|e| e.at(self.read))?;
+
+            if self.read + data.len() > self.options.max_bytes {
+                let max = self.options.max_bytes;
+                return Err(Error::limit_exceeded("stream length", max).at(self.read));
+            }
+
+            self.read += data.len();
+
+            self.buffer.push(data);
+        }
+
+        Ok(())
+    }
+
+    async fn buffer_string(
+        &mut self,
+        begin: &'static [u8],
+        end: &'static [u8],
+    ) -> Result<Bytes, Error> {
+        self.expect_delimiter(begin).await?;
+
+        let max = self.options.max_string_len;
+        self.buffer_escaped(end[0], max, "string length").await
+    }
+
+    /// Consume and unescape bytes up to the next unescaped `end` delimiter,
+    /// returning an error if there are more than `max` of them.
+    async fn buffer_escaped(&mut self, end: u8, max: usize, limit: &str) -> Result<Bytes, Error> {
+        while self.buffer.is_empty() && !self.source.is_terminated() {
+            self.buffer().await?;
+        }
+
+        if let (len, true) = self.scan_literal(end) {
+            if len <= max {
+                // there is nothing to unescape, so return a slice of the source chunk
+                let bytes = self.consume(len);
+                self.advance(1); // process the end delimiter
+                return Ok(bytes);
+            }
+        }
+
+        let mut bytes = BytesMut::new();
+        if self.unescape(end, max, Some(&mut bytes)).await? {
+            self.advance(1); // process the end delimiter
+            Ok(bytes.into())
+        } else {
+            Err(Error::limit_exceeded(limit, max).at(self.offset()))
+        }
+    }
+
+    /// Consume the chunks of a length-prefixed `u8` array,
+    /// returning an error if there are more than `max` bytes in total.
+    async fn buffer_chunks(&mut self, max: usize) -> Result<Bytes, Error> {
+        let mut chunks = Vec::new();
+        let mut total = 0usize;
+
+        loop {
+            let offset = self.offset();
+            let len = self.parse_len().await?;
+
+            if len == 0 {
+                break;
+            }
+
+            total = total.saturating_add(len);
+            if total > max {
+                return Err(Error::limit_exceeded("array length", max).at(offset));
+            }
+
+            while self.buffer.len() < len && !self.source.is_terminated() {
+                self.buffer().await?;
+            }
+
+            if self.buffer.len() < len {
+                return Err(Error::unexpected_end().at(self.read));
+            }
+
+            chunks.push(self.consume(len));
+        }
+
+        if chunks.len() == 1 {
+            Ok(chunks.pop().expect("chunk"))
+        } else {
+            Ok(chunks.concat().into())
+        }
+    }
+
+    /// Skip the chunks of a length-prefixed array whose elements are `size` bytes long.
+    async fn ignore_chunks(&mut self, size: usize) -> Result<(), Error> {
+        loop {
+            let offset = self.offset();
+            let len = self.parse_len().await?;
+
+            if len == 0 {
+                return Ok(());
+            }
+
+            let mut remaining = len
+                .checked_mul(size)
+                .ok_or_else(|| Error::chunk_overflow().at(offset))?;
+
+            while remaining > 0 {
+                while self.buffer.is_empty() && !self.source.is_terminated() {
+                    self.buffer().await?;
+                }
+
+                if self.buffer.is_empty() {
+                    return Err(Error::unexpected_end().at(self.read));
+                }
+
+                let n = Ord::min(remaining, self.buffer.len());
+                self.advance(n);
+                remaining -= n;
+            }
+        }
+    }
+
+    async fn ignore_string(
+        &mut self,
+        begin: &'static [u8],
+        end: &'static [u8],
+    ) -> Result<(), Error> {
+        self.expect_delimiter(begin).await?;
+        self.unescape(end[0], usize::MAX, None).await?;
+        self.advance(1); // process the end delimiter
+        Ok(())
+    }
+
+    /// Return the length of the prefix of the first chunk in the buffer which contains no escape
+    /// character or `end` delimiter, and whether that prefix is followed by the `end` delimiter.
+    fn scan_literal(&self, end: u8) -> (usize, bool) {
+        let chunk = self.buffer.chunk();
+
+        match memchr2(end, ESCAPE[0], chunk) {
+            Some(i) => (i, chunk[i] == end),
+            None => (chunk.len(), false),
+        }
+    }
+
+    /// Consume escaped bytes up to the next unescaped `end` delimiter, or until `max` unescaped
+    /// bytes have been consumed, copying the unescaped bytes to `output`, if any.
+    ///
+    /// Returns `true` if the next byte in the buffer is the `end` delimiter, which is not consumed.
+    async fn unescape(
+        &mut self,
+        end: u8,
+        max: usize,
+        mut output: Option<&mut BytesMut>,
+    ) -> Result<bool, Error> {
+        let mut len = 0;
+        let mut escaped = false;
+
+        loop {
+            while self.buffer.is_empty() && !self.source.is_terminated() {
+                self.buffer().await?;
+            }
+
+            if self.buffer.is_empty() {
+                return Err(Error::unexpected_end().at(self.read));
+            }
+
+            let chunk = self.buffer.chunk();
+            let mut i = 0;
+            let mut found = false;
+
+            while i < chunk.len() {
+                if escaped {
+                    if let Some(output) = output.as_deref_mut() {
+                        output.put_u8(chunk[i]);
+                    }
+
+                    escaped = false;
+                    len += 1;
+                    i += 1;
+                    continue;
+                }
+
+                // copy the run of bytes up to the next control character in bulk
+                let rest = &chunk[i..];
+                let plain = memchr2(end, ESCAPE[0], rest).unwrap_or(rest.len());
+                let n = Ord::min(plain, max - len);
+
+                if let Some(output) = output.as_deref_mut() {
+                    output.put_slice(&rest[..n]);
+                }
+
+                len += n;
+                i += n;
+
+                if n < plain || i == chunk.len() {
+                    break;
+                } else if chunk[i] == end {
+                    found = true;
+                    break;
+                } else if len == max {
+                    break;
+                } else {
+                    escaped = true;
+                    i += 1;
+                }
+            }
+
+            self.advance(i);
+
+            if found {
+                return Ok(true);
+            } else if len == max && !escaped && !self.buffer.is_empty() {
+                return Ok(false);
+            }
+        }
+    }
+
+    /// Consume the delimiter and type bit which begin an array of the given `dtype`,
+    /// returning `true` if the array is length-prefixed.
+    async fn begin_array(&mut self, dtype: Type) -> Result<bool, Error> {
+        while self.buffer.is_empty() && !self.source.is_terminated() {
+            self.buffer().await?;
+        }
+
+        let prefixed = self.buffer.first() == Some(ARRAY_PREFIXED[0]);
+        if prefixed {
+            self.advance(1);
+        } else {
+            self.expect_delimiter(ARRAY_DELIMIT).await?;
+        }
+
+        while self.buffer.is_empty() && !self.source.is_terminated() {
+            self.buffer().await?;
+        }
+
+        match self.buffer.first() {
+            None => Err(Error::unexpected_end().at(self.read)),
+            Some(bit) if Some(bit) == dtype.to_u8() => {
+                self.advance(1);
+                Ok(prefixed)
+            }
+            Some(bit) => Err(Error::type_mismatch(bit, dtype).at(self.offset())),
+        }
+    }
+
+    /// Parse the varint length of a chunk of a length-prefixed array.
+    async fn parse_len(&mut self) -> Result<usize, Error> {
+        let offset = self.offset();
+        let len = self.parse_varint().await?;
+        usize::try_from(len).map_err(|_| Error::chunk_overflow().at(offset))
+    }
+
+    /// Parse a LEB128 varint, not including any type bit.
+    async fn parse_varint(&mut self) -> Result<u128, Error> {
+        let offset = self.offset();
+        let mut len = 0;
+
+        loop {
+            while self.buffer.len() <= len && !self.source.is_terminated() {
+                self.buffer().await?;
+            }
+
+            match self.buffer.get(len) {
+                Some(byte) if byte & 0x80 == 0 => break,
+                Some(_) if len + 1 < varint::MAX_LEN => len += 1,
+                Some(_) => return Err(Error::varint_overflow().at(offset)),
+                None => return Err(Error::unexpected_end().at(self.read)),
+            }
+        }
+
+        let bytes = self.consume(len + 1);
+        varint::decode(&bytes).ok_or_else(|| Error::varint_overflow().at(offset))
+    }
+
+    /// Skip the magic header set in the [`DecoderOptions`], if any.
+    async fn expect_header(&mut self) -> Result<(), Error> {
+        let header = match self.options.magic_header {
+            Some(header) => header,
+            None => return Ok(()),
+        };
+
+        while self.buffer.len() < header.len() && !self.source.is_terminated() {
+            self.buffer().await?;
+        }
+
+        let matches = header
+            .iter()
+            .enumerate()
+            .all(|(i, byte)| self.buffer.get(i) == Some(*byte));
+
+        if matches {
+            self.advance(header.len());
+            Ok(())
+        } else {
+            Err(Error::missing_header(header).at(self.offset()))
+        }
+    }
+
+    async fn expect_delimiter(&mut self, delimiter: &[u8]) -> Result<(), Error> {
+        while self.buffer.is_empty() && !self.source.is_terminated() {
+            self.buffer().await?;
+        }
+
+        let first = match self.buffer.first() {
+            Some(byte) => byte,
+            None => return Err(Error::unexpected_end().at(self.read)),
+        };
+
+        if first == delimiter[0] {
+            self.advance(1);
+            Ok(())
+        } else {
+            fn char_to_string(c: u8) -> String {
+                if c < ' ' as u8 {
+                    c.to_string()
+                } else {
+                    (c as char).to_string()
+                }
+            }
+
+            let actual = char_to_string(first);
+            let expected = char_to_string(delimiter[0]);
+
+            let snippet = self.contents(SNIPPET_LEN);
+            let message = format!(
+                "unexpected delimiter {}, expected {} at {}",
+                actual, expected, snippet
+            );
+
+            Err(Error::new(ErrorKind::UnexpectedDelimiter, message).at(self.offset()))
+        }
+    }
+
+    #[async_recursion]
+    async fn ignore_value(&mut self) -> Result<(), Error> {
+        while self.buffer.is_empty() && !self.source.is_terminated() {
+            self.buffer().await?;
+        }
+
+        let first = match self.buffer.first() {
+            Some(byte) => byte,
+            None => return Err(Error::unexpected_end().at(self.read)),
+        };
+
+        match &[first] {
+            ARRAY_DELIMIT | ARRAY_PREFIXED => {
+                while self.buffer.len() < 2 && !self.source.is_terminated() {
+                    self.buffer().await?;
+                }
+
+                let dtype = match self.buffer.get(1) {
+                    Some(byte) => byte,
+                    None => return Err(Error::unexpected_end().at(self.read)),
+                };
+
+                match Type::from_u8(dtype) {
+                    Some(Type::None | Type::VarInt | Type::VarUInt) | None => {
+                        let offset = self.offset() + 1;
+                        Err(Error::unknown_type_bit(dtype).at(offset))
+                    }
+                    Some(dtype) if first == ARRAY_PREFIXED[0] => {
+                        self.advance(2);
+                        self.ignore_chunks(dtype.size()).await
+                    }
+                    Some(_) => self.ignore_string(ARRAY_DELIMIT, ARRAY_DELIMIT).await,
+                }
+            }
+            LIST_BEGIN => {
+                self.descend()?;
+                let result = self.ignore_seq(LIST_BEGIN, LIST_END, 1).await;
+                self.ascend();
+                result
+            }
+            MAP_BEGIN => {
+                self.descend()?;
+                let result = self.ignore_seq(MAP_BEGIN, MAP_END, 2).await;
+                self.ascend();
+                result
+            }
+            STRING_DELIMIT => self.ignore_string(STRING_DELIMIT, STRING_DELIMIT).await,
+            &[dtype] => match Type::from_u8(dtype)
+                .ok_or_else(|| Error::unknown_type_bit(dtype).at(self.offset()))?
+            {
+                Type::None => self.parse_unit().await,
+                Type::Bool => self.parse_element::<bool>().await.map(|_| ()),
+                Type::F32 => self.parse_element::<f32>().await.map(|_| ()),
+                Type::F64 => self.parse_element::<f64>().await.map(|_| ()),
+                Type::I8 => self.parse_element::<i8>().await.map(|_| ()),
+                Type::I16 => self.parse_element::<i16>().await.map(|_| ()),
+                Type::I32 => self.parse_element::<i32>().await.map(|_| ()),
+                Type::I64 => self.parse_element::<i64>().await.map(|_| ()),
+                Type::U8 => self.parse_element::<u8>().await.map(|_| ()),
+                Type::U16 => self.parse_element::<u16>().await.map(|_| ()),
+                Type::U32 => self.parse_element::<u32>().await.map(|_| ()),
+                Type::U64 => self.parse_element::<u64>().await.map(|_| ()),
+                Type::I128 => self.parse_element::<i128>().await.map(|_| ()),
+                Type::U128 => self.parse_element::<u128>().await.map(|_| ()),
+                Type::F16 | Type::BF16 => self.skip_scalar(2).await,
+                Type::Uuid => self.parse_element::<Uuid>().await.map(|_| ()),
+                Type::VarInt | Type::VarUInt => {
+                    self.advance(1);
+                    self.parse_varint().await.map(|_| ())
+                }
+            },
+        }
+    }
+
+    /// Skip a list or map, whose entries each consist of `arity` values.
+    async fn ignore_seq(
+        &mut self,
+        begin: &'static [u8],
+        end: &'static [u8],
+        arity: usize,
+    ) -> Result<(), Error> {
+        self.expect_delimiter(begin).await?;
+
+        while !self.maybe_delimiter(end).await? {
+            for _ in 0..arity {
+                self.ignore_value().await?;
+            }
+        }
+
+        Ok(())
+    }
+
+    async fn maybe_delimiter(&mut self, delimiter: &'static [u8]) -> Result<bool, Error> {
+        while self.buffer.is_empty() && !self.source.is_terminated() {
+            self.buffer().await?;
+        }
+
+        if self.buffer.first() == Some(delimiter[0]) {
+            self.advance(1);
+            Ok(true)
+        } else {
+            Ok(false)
+        }
+    }
+
+    /// Visit a varint as a 64-bit integer, or as a 128-bit [`Native`] integer if it's too long to
+    /// have been encoded from a 64-bit integer, so that it can be re-encoded as the same varint.
+    async fn decode_varint<V: Visitor>(&mut self, visitor: V) -> Result<V::Value, Error> {
+        let signed = self.buffer.first() == Type::VarInt.to_u8();
+        self.advance(1);
+        let n = self.parse_varint().await?;
+
+        // the encoder only writes a varint which is shorter than the fixed-width integer,
+        // so one which is at least as long as an i64 was encoded from a 128-bit integer
+        let wide = varint::encoded_len(n) >= mem::size_of::<i64>();
+
+        match (signed, wide) {
+            (true, false) => visitor.visit_i64(varint::unzigzag(n) as i64),
+            (false, false) => visitor.visit_u64(n as u64),
+            (true, true) => Ok(visit_native(visitor, Native::I128(varint::unzigzag(n)))),
+            (false, true) => Ok(visit_native(visitor, Native::U128(n))),
+        }
+    }
+
+    /// Read every element of an array of `T` into memory, e.g. to visit it as a [`Native`] value.
+    async fn read_array<T: Element + Copy + Default + Send>(&mut self) -> Result<Vec<T>, Error> {
+        let mut access = ArrayAccess::<R, T>::new(self).await?;
+        let mut array = Vec::new();
+        let mut buffer = vec![T::default(); 256];
+
+        loop {
+            let len = de::ArrayAccess::buffer(&mut access, &mut buffer).await?;
+            if len == 0 {
+                break;
+            } else {
+                array.extend_from_slice(&buffer[..len]);
+            }
+        }
+
+        Ok(array)
+    }
+
+    async fn parse_element<N: Element>(&mut self) -> Result<N, Error> {
+        while self.buffer.is_empty() && !self.source.is_terminated() {
+            self.buffer().await?;
+        }
+
+        let offset = self.offset();
+
+        let dtype = match self.buffer.first() {
+            Some(byte) => byte,
+            None => return Err(Error::unexpected_end().at(offset)),
+        };
+
+        match Type::from_u8(dtype) {
+            Some(Type::VarInt) => return self.parse_varint_as(true).await,
+            Some(Type::VarUInt) => return self.parse_varint_as(false).await,
+            _ => {}
+        }
+
+        while self.buffer.len() <= N::SIZE && !self.source.is_terminated() {
+            self.buffer().await?;
+        }
+
+        if Some(dtype) != N::dtype().to_u8() {
+            return Err(Error::type_mismatch(dtype, N::dtype()).at(offset));
+        }
+
+        if self.buffer.len() <= N::SIZE {
+            return Err(Error::unexpected_end().at(self.read));
+        }
+
+        self.advance(1);
+        let bytes = self.consume(N::SIZE);
+        N::parse(&bytes).map_err(|e: Error| e.at(offset))
+    }
+
+    /// Parse a variable-length integer as an `N`, failing if `N` is not an integer type
+    /// or its value is out of the range of an `N`.
+    async fn parse_varint_as<N: Element>(&mut self, signed: bool) -> Result<N, Error> {
+        let offset = self.offset();
+        let dtype = if signed { Type::VarInt } else { Type::VarUInt };
+
+        if !N::dtype().is_integer() {
+            return Err(Error::at(
+                de::Error::invalid_type(dtype, N::dtype()),
+                offset,
+            ));
+        }
+
+        self.advance(1);
+        let n = self.parse_varint().await?;
+
+        let element = if signed {
+            N::from_varint(varint::unzigzag(n))
+        } else {
+            N::from_unsigned_varint(n)
+        };
+
+        element.ok_or_else(|| {
+            let n = if signed {
+                varint::unzigzag(n).to_string()
+            } else {
+                n.to_string()
+            };
+
+            let expected = format!("a value in the range of {}", N::dtype());
+            Error::at(de::Error::invalid_value(n, expected), offset)
+        })
+    }
+
+    /// Parse a scalar of type `T`, or of type `E` if that's what the stream contains,
+    /// failing if its value is out of the range of a `T`.
+    async fn parse_as<E, T>(&mut self) -> Result<T, Error>
+    where
+        E: Element + Copy + fmt::Display,
+        T: Element + TryFrom<E>,
+    {
+        while self.buffer.is_empty() && !self.source.is_terminated() {
+            self.buffer().await?;
+        }
+
+        if self.buffer.first() == E::dtype().to_u8() {
+            let offset = self.offset();
+            let element = self.parse_element::<E>().await?;
+            cast_element(element).map_err(|e| e.at(offset))
+        } else {
+            self.parse_element().await
+        }
+    }
+
+    /// Skip a scalar whose encoding (after its type bit) is `size` bytes long.
+    async fn skip_scalar(&mut self, size: usize) -> Result<(), Error> {
+        while self.buffer.len() <= size && !self.source.is_terminated() {
+            self.buffer().await?;
+        }
+
+        if self.buffer.len() <= size {
+            return Err(Error::unexpected_end().at(self.read));
+        }
+
+        self.advance(1 + size);
+        Ok(())
+    }
+
+    /// Return `true` if the next value in the stream is a `T`.
+    #[cfg(feature = "half")]
+    async fn peek_type<T: Element>(&mut self) -> Result<bool, Error> {
+        while self.buffer.is_empty() && !self.source.is_terminated() {
+            self.buffer().await?;
+        }
+
+        Ok(self.buffer.first() == T::dtype().to_u8())
+    }
+
+    /// Return `true` if the next value in the stream is an array of `T`.
+    async fn peek_array_type<T: Element>(&mut self) -> Result<bool, Error> {
+        while self.buffer.len() < 2 && !self.source.is_terminated() {
+            self.buffer().await?;
+        }
+
+        let is_array = matches!(
+            self.buffer.first(),
+            Some(first) if &[first] == ARRAY_DELIMIT || &[first] == ARRAY_PREFIXED
+        );
+
+        Ok(is_array && self.buffer.get(1) == T::dtype().to_u8())
+    }
+
+    async fn parse_string(&mut self) -> Result<String, Error> {
+        let offset = self.offset();
+        let s = self.buffer_string(STRING_DELIMIT, STRING_DELIMIT).await?;
+        String::from_utf8(s.into()).map_err(|cause| Error::invalid_utf8(cause).at(offset))
+    }
+
+    async fn parse_unit(&mut self) -> Result<(), Error> {
+        while self.buffer.is_empty() && !self.source.is_terminated() {
+            self.buffer().await?;
+        }
+
+        let first = match self.buffer.first() {
+            Some(byte) => byte,
+            None => return Err(Error::unexpected_end().at(self.read)),
+        };
+
+        match first {
+            byte if Some(byte) == Type::None.to_u8() => {
+                self.advance(1);
+                Ok(())
+            }
+            other => Err(Error::type_mismatch(other, Type::None).at(self.offset())),
+        }
+    }
+}
+
+impl<R: Read> de::Decoder for Decoder<R> {
+    type Error = Error;
+
+    async fn decode_any<V: Visitor>(&mut self, visitor: V) -> Result<V::Value, Self::Error> {
+        while self.buffer.is_empty() && !self.source.is_terminated() {
+            self.buffer().await?;
+        }
+
+        let first = match self.buffer.first() {
+            Some(byte) => byte,
+            None => return Err(Error::unexpected_end().at(self.read)),
+        };
+
+        let offset = self.offset();
+        let type_from =
+            |bit: u8| Type::from_u8(bit).ok_or_else(|| Error::unknown_type_bit(bit).at(offset));
+
+        match &[first] {
+            ARRAY_DELIMIT | ARRAY_PREFIXED => {
+                while self.buffer.len() < 2 && !self.source.is_terminated() {
+                    self.buffer().await?;
+                }
+
+                let dtype = match self.buffer.get(1) {
+                    Some(byte) => byte,
+                    None => return Err(Error::unexpected_end().at(self.read)),
+                };
+
+                match type_from(dtype)? {
+                    Type::Bool => self.decode_array_bool(visitor).await,
+                    #[cfg(feature = "half")]
+                    Type::F16 if visits_native::<V>() => {
+                        let array = self.read_array().await?;
+                        Ok(visit_native(visitor, Native::ArrayF16(array)))
+                    }
+                    #[cfg(feature = "half")]
+                    Type::BF16 if visits_native::<V>() => {
+                        let array = self.read_array().await?;
+                        Ok(visit_native(visitor, Native::ArrayBF16(array)))
+                    }
+                    Type::F16 | Type::BF16 | Type::F32 => self.decode_array_f32(visitor).await,
+                    Type::F64 => self.decode_array_f64(visitor).await,
+                    Type::I8 => self.decode_array_i8(visitor).await,
+                    Type::I16 => self.decode_array_i16(visitor).await,
+                    Type::I32 => self.decode_array_i32(visitor).await,
+                    Type::I128 if visits_native::<V>() => {
+                        let array = self.read_array().await?;
+                        Ok(visit_native(visitor, Native::ArrayI128(array)))
+                    }
+                    Type::I64 | Type::I128 => self.decode_array_i64(visitor).await,
+                    Type::U8 => self.decode_array_u8(visitor).await,
+                    Type::U16 => self.decode_array_u16(visitor).await,
+                    Type::U32 => self.decode_array_u32(visitor).await,
+                    Type::U128 if visits_native::<V>() => {
+                        let array = self.read_array().await?;
+                        Ok(visit_native(visitor, Native::ArrayU128(array)))
+                    }
+                    Type::U64 | Type::U128 => self.decode_array_u64(visitor).await,
+                    dtype => {
+                        let error = de::Error::invalid_type(dtype, "a supported array type");
+                        Err(Error::at(error, offset))
+                    }
+                }
+            }
+            LIST_BEGIN => self.decode_seq(visitor).await,
+            MAP_BEGIN => self.decode_map(visitor).await,
+            STRING_DELIMIT => self.decode_string(visitor).await,
+            [dtype] => match type_from(*dtype)? {
+                Type::None => self.decode_unit(visitor).await,
+                Type::Bool => self.decode_bool(visitor).await,
+                #[cfg(feature = "half")]
+                Type::F16 if visits_native::<V>() => {
+                    let f = self.decode_f16().await?;
+                    Ok(visit_native(visitor, Native::F16(f)))
+                }
+                #[cfg(feature = "half")]
+                Type::BF16 if visits_native::<V>() => {
+                    let f = self.decode_bf16().await?;
+                    Ok(visit_native(visitor, Native::BF16(f)))
+                }
+                Type::F16 | Type::BF16 | Type::F32 => self.decode_f32(visitor).await,
+                Type::F64 => self.decode_f64(visitor).await,
+                Type::I8 => self.decode_i8(visitor).await,
+                Type::I16 => self.decode_i16(visitor).await,
+                Type::I32 => self.decode_i32(visitor).await,
+                Type::I128 if visits_native::<V>() => {
+                    let i = self.decode_i128().await?;
+                    Ok(visit_native(visitor, Native::I128(i)))
+                }
+                Type::I64 | Type::I128 => self.decode_i64(visitor).await,
+                Type::U8 => self.decode_u8(visitor).await,
+                Type::U16 => self.decode_u16(visitor).await,
+                Type::U32 => self.decode_u32(visitor).await,
+                Type::U128 if visits_native::<V>() => {
+                    let u = self.decode_u128().await?;
+                    Ok(visit_native(visitor, Native::U128(u)))
+                }
+                Type::U64 | Type::U128 => self.decode_u64(visitor).await,
+                Type::Uuid if visits_native::<V>() => {
+                    let uuid = self.parse_element().await?;
+                    Ok(visit_native(visitor, Native::Uuid(uuid)))
+                }
+                Type::Uuid => self.decode_uuid(visitor).await,
+                Type::VarInt | Type::VarUInt if visits_native::<V>() => {
+                    self.decode_varint(visitor).await
+                }
+                Type::VarInt => self.decode_i64(visitor).await,
+                Type::VarUInt => self.decode_u64(visitor).await,
+            },
+        }
+    }
+
+    async fn decode_bool<V: Visitor>(&mut self, visitor: V) -> Result<V::Value, Self::Error> {
+        let b = self.parse_element().await?;
+        visitor.visit_bool(b)
+    }
+
+    async fn decode_bytes<V: Visitor>(&mut self, visitor: V) -> Result<V::Value, Self::Error> {
+        match self.literal_byte_buffer().await? {
+            Some(bytes) => {
+                let access = ByteBufferAccess { bytes };
+                visitor.visit_array_u8(access).boxed().await
+            }
+            None => self.decode_array_u8(visitor).await,
+        }
+    }
+
+    async fn decode_i8<V: Visitor>(&mut self, visitor: V) -> Result<V::Value, Self::Error> {
+        let i = self.parse_element().await?;
+        visitor.visit_i8(i)
+    }
+
+    async fn decode_i16<V: Visitor>(&mut self, visitor: V) -> Result<V::Value, Self::Error> {
+        let i = self.parse_element().await?;
+        visitor.visit_i16(i)
+    }
+
+    async fn decode_i32<V: Visitor>(&mut self, visitor: V) -> Result<V::Value, Self::Error> {
+        let i = self.parse_element().await?;
+        visitor.visit_i32(i)
+    }
+
+    async fn decode_i64<V: Visitor>(&mut self, visitor: V) -> Result<V::Value, Self::Error> {
+        let i = self.parse_as::<i128, i64>().await?;
+        visitor.visit_i64(i)
+    }
+
+    async fn decode_u8<V: Visitor>(&mut self, visitor: V) -> Result<V::Value, Self::Error> {
+        let u = self.parse_element().await?;
+        visitor.visit_u8(u)
+    }
+
+    async fn decode_u16<V: Visitor>(&mut self, visitor: V) -> Result<V::Value, Self::Error> {
+        let u = self.parse_element().await?;
+        visitor.visit_u16(u)
+    }
+
+    async fn decode_u32<V: Visitor>(&mut self, visitor: V) -> Result<V::Value, Self::Error> {
+        let u = self.parse_element().await?;
+        visitor.visit_u32(u)
+    }
+
+    async fn decode_u64<V: Visitor>(&mut self, visitor: V) -> Result<V::Value, Self::Error> {
+        let u = self.parse_as::<u128, u64>().await?;
+        visitor.visit_u64(u)
+    }
+
+    async fn decode_f32<V: Visitor>(&mut self, visitor: V) -> Result<V::Value, Self::Error> {
+        #[cfg(feature = "half")]
+        if self.peek_type::<half::f16>().await? {
+            let f = self.parse_element::<half::f16>().await?;
+            return visitor.visit_f32(f.to_f32());
+        } else if self.peek_type::<half::bf16>().await? {
+            let f = self.parse_element::<half::bf16>().await?;
+            return visitor.visit_f32(f.to_f32());
+        }
+
+        let f = self.parse_element().await?;
+        visitor.visit_f32(f)
+    }
+
+    async fn decode_f64<V: Visitor>(&mut self, visitor: V) -> Result<V::Value, Self::Error> {
+        let f = self.parse_element().await?;
+        visitor.visit_f64(f)
+    }
+
+    async fn decode_array_bool<V: Visitor>(&mut self, visitor: V) -> Result<V::Value, Self::Error> {
+        let access = ArrayAccess::new(self).await?;
+        // TODO: remove boxing when https://github.com/rust-lang/rust/issues/100013 is resolved
+        visitor.visit_array_bool(access).boxed().await
+    }
+
+    async fn decode_array_i8<V: Visitor>(&mut self, visitor: V) -> Result<V::Value, Self::Error> {
+        let access = ArrayAccess::new(self).await?;
+        visitor.visit_array_i8(access).boxed().await
+    }
+
+    async fn decode_array_i16<V: Visitor>(&mut self, visitor: V) -> Result<V::Value, Self::Error> {
+        let access = ArrayAccess::new(self).await?;
+        visitor.visit_array_i16(access).boxed().await
+    }
+
+    async fn decode_array_i32<V: Visitor>(&mut self, visitor: V) -> Result<V::Value, Self::Error> {
+        let access = ArrayAccess::new(self).await?;
+        visitor.visit_array_i32(access).boxed().await
+    }
+
+    async fn decode_array_i64<V: Visitor>(&mut self, visitor: V) -> Result<V::Value, Self::Error> {
+        if self.peek_array_type::<i128>().await? {
+            let access = CastArrayAccess::<_, i128>::new(ArrayAccess::new(self).await?);
+            visitor.visit_array_i64(access).boxed().await
+        } else {
+            let access = ArrayAccess::new(self).await?;
+            visitor.visit_array_i64(access).boxed().await
+        }
+    }
+
+    async fn decode_array_u8<V: Visitor>(&mut self, visitor: V) -> Result<V::Value, Self::Error> {
+        let access = ArrayAccess::new(self).await?;
+        visitor.visit_array_u8(access).boxed().await
+    }
+
+    async fn decode_array_u16<V: Visitor>(&mut self, visitor: V) -> Result<V::Value, Self::Error> {
+        let access = ArrayAccess::new(self).await?;
+        visitor.visit_array_u16(access).boxed().await
+    }
+
+    async fn decode_array_u32<V: Visitor>(&mut self, visitor: V) -> Result<V::Value, Self::Error> {
+        let access = ArrayAccess::new(self).await?;
+        visitor.visit_array_u32(access).boxed().await
+    }
+
+    async fn decode_array_u64<V: Visitor>(&mut self, visitor: V) -> Result<V::Value, Self::Error> {
+        if self.peek_array_type::<u128>().await? {
+            let access = CastArrayAccess::<_, u128>::new(ArrayAccess::new(self).await?);
+            visitor.visit_array_u64(access).boxed().await
+        } else {
+            let access = ArrayAccess::new(self).await?;
+            visitor.visit_array_u64(access).boxed().await
+        }
+    }
+
+    async fn decode_array_f32<V: Visitor>(&mut self, visitor: V) -> Result<V::Value, Self::Error> {
+        #[cfg(feature = "half")]
+        if self.peek_array_type::<half::f16>().await? {
+            let access = CastArrayAccess::<_, half::f16>::new(ArrayAccess::new(self).await?);
+            return visitor.visit_array_f32(access).boxed().await;
+        } else if self.peek_array_type::<half::bf16>().await? {
+            let access = CastArrayAccess::<_, half::bf16>::new(ArrayAccess::new(self).await?);
+            return visitor.visit_array_f32(access).boxed().await;
+        }
+
+        let access = ArrayAccess::new(self).await?;
+        visitor.visit_array_f32(access).boxed().await
+    }
+
+    async fn decode_array_f64<V: Visitor>(&mut self, visitor: V) -> Result<V::Value, Self::Error> {
+        let access = ArrayAccess::new(self).await?;
+        visitor.visit_array_f64(access).boxed().await
+    }
+
+    async fn decode_string<V: Visitor>(&mut self, visitor: V) -> Result<V::Value, Self::Error> {
+        let s = self.parse_string().await?;
+        visitor.visit_string(s)
+    }
+
+    async fn decode_option<V: Visitor>(&mut self, visitor: V) -> Result<V::Value, Self::Error> {
+        while self.buffer.is_empty() && !self.source.is_terminated() {
+            self.buffer().await?;
+        }
+
+        let first = match self.buffer.first() {
+            Some(byte) => byte,
+            None => return Err(Error::unexpected_end().at(self.read)),
+        };
+
+        if Some(first) == Type::None.to_u8() {
+            self.advance(1);
+            visitor.visit_none()
+        } else {
+            visitor.visit_some(self).await
+        }
+    }
+
+    async fn decode_map<V: Visitor>(&mut self, visitor: V) -> Result<V::Value, Self::Error> {
+        self.descend()?;
+        let result = match MapAccess::new(self, None).await {
+            Ok(access) => visitor.visit_map(access).boxed().await,
+            Err(cause) => Err(cause),
+        };
+
+        self.ascend();
+        result
+    }
+
+    async fn decode_seq<V: Visitor>(&mut self, visitor: V) -> Result<V::Value, Self::Error> {
+        self.descend()?;
+        let result = match SeqAccess::new(self, None).await {
+            Ok(access) => visitor.visit_seq(access).boxed().await,
+            Err(cause) => Err(cause),
+        };
+
+        self.ascend();
+        result
+    }
+
+    async fn decode_tuple<V: Visitor>(
+        &mut self,
+        len: usize,
+        visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        self.descend()?;
+        let result = match SeqAccess::new(self, Some(len)).await {
+            Ok(access) => visitor.visit_seq(access).boxed().await,
+            Err(cause) => Err(cause),
+        };
+
+        self.ascend();
+        result
+    }
+
+    async fn decode_unit<V: Visitor>(&mut self, visitor: V) -> Result<V::Value, Self::Error> {
+        self.parse_unit().await?;
+        visitor.visit_unit()
+    }
+
+    async fn decode_uuid<V: Visitor>(&mut self, visitor: V) -> Result<V::Value, Self::Error> {
+        while self.buffer.is_empty() && !self.source.is_terminated() {
+            self.buffer().await?;
+        }
+
+        if self.buffer.first() == Type::Uuid.to_u8() {
+            let uuid = self.parse_element::<Uuid>().await?;
+            // there is no visit_uuid method, so visit the UUID as an array of bytes
+            // (unless it's a VisitNative visitor, when it's called via decode_any)
+            let access = UuidAccess { uuid: Some(uuid) };
+            visitor.visit_array_u8(access).boxed().await
+        } else {
+            // a UUID may also be encoded as a u8 array, as it was prior to the UUID type bit
+            self.decode_array_u8(visitor).await
+        }
+    }
+
+    async fn decode_ignored_any<V: Visitor>(
+        &mut self,
+        visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        self.ignore_value().await?;
+        visitor.visit_unit()
+    }
+}
+
+/// Decode the given TBON-encoded stream of bytes into an instance of `T` using the given context.
+pub async fn decode<S: Stream<Item = Bytes> + Send + Unpin, T: FromStream>(
+    context: T::Context,
+    source: S,
+) -> Result<T, Error> {
+    decode_with_options(context, source, DecoderOptions::default()).await
+}
+
+/// Decode the given TBON-encoded stream of bytes into an instance of `T` using the given context,
+/// enforcing the limits set in the given `options`.
+pub async fn decode_with_options<S: Stream<Item = Bytes> + Send + Unpin, T: FromStream>(
+    context: T::Context,
+    source: S,
+    options: DecoderOptions,
+) -> Result<T, Error> {
+    let source = source.map(Result::<Bytes, Error>::Ok);
+    let mut decoder = Decoder::from_stream_with_options(source, options);
+    decode_value(context, &mut decoder).await
+}
+
+/// Decode the given TBON-encoded stream of bytes into an instance of `T` using the given context.
+pub async fn try_decode<
+    E: fmt::Display,
+    S: Stream<Item = Result<Bytes, E>> + Send + Unpin,
+    T: FromStream,
+>(
+    context: T::Context,
+    source: S,
+) -> Result<T, Error> {
+    try_decode_with_options(context, source, DecoderOptions::default()).await
+}
+
+/// Decode the given TBON-encoded stream of bytes into an instance of `T` using the given context,
+/// enforcing the limits set in the given `options`.
+pub async fn try_decode_with_options<
+    E: fmt::Display,
+    S: Stream<Item = Result<Bytes, E>> + Send + Unpin,
+    T: FromStream,
+>(
+    context: T::Context,
+    source: S,
+    options: DecoderOptions,
+) -> Result<T, Error> {
+    let source = source.map_err(Error::io);
+    let mut decoder = Decoder::from_stream_with_options(source, options);
+    decode_value(context, &mut decoder).await
+}
+
+/// Decode the given TBON-encoded stream of bytes into an instance of `T` using the given context.
+#[cfg(feature = "tokio-io")]
+pub async fn read_from<R: AsyncReadExt + Send + Unpin, T: FromStream>(
+    context: T::Context,
+    source: R,
+) -> Result<T, Error> {
+    read_from_with_options(context, source, DecoderOptions::default()).await
+}
+
+/// Decode the given TBON-encoded stream of bytes into an instance of `T` using the given context,
+/// enforcing the limits set in the given `options`.
+#[cfg(feature = "tokio-io")]
+pub async fn read_from_with_options<R: AsyncReadExt + Send + Unpin, T: FromStream>(
+    context: T::Context,
+    source: R,
+    options: DecoderOptions,
+) -> Result<T, Error> {
+    let mut decoder = Decoder::from_reader_with_options(source, options);
+    decode_value(context, &mut decoder).await
+}
+
+/// Decode the given TBON-encoded [`futures::io::AsyncRead`] source into an instance of `T`
+/// using the given context.
+#[cfg(feature = "futures-io")]
+pub async fn read_from_futures<R, T>(context: T::Context, source: R) -> Result<T, Error>
+where
+    R: futures::io::AsyncRead + Send + Unpin,
+    T: FromStream,
+{
+    read_from_futures_with_options(context, source, DecoderOptions::default()).await
+}
+
+/// Decode the given TBON-encoded [`futures::io::AsyncRead`] source into an instance of `T`
+/// using the given context, enforcing the limits set in the given `options`.
+#[cfg(feature = "futures-io")]
+pub async fn read_from_futures_with_options<R, T>(
+    context: T::Context,
+    source: R,
+    options: DecoderOptions,
+) -> Result<T, Error>
+where
+    R: futures::io::AsyncRead + Send + Unpin,
+    T: FromStream,
+{
+    let mut decoder = Decoder::from_futures_reader_with_options(source, options);
+    decode_value(context, &mut decoder).await
+}
+
+/// Decode an instance of `T` from the given TBON-encoded slice, without an async runtime.
+///
+/// The slice is copied into a new buffer before decoding. Use [`from_bytes`] to decode
+/// a [`Bytes`] buffer without copying it.
+pub fn from_slice<T: FromStream>(context: T::Context, slice: &[u8]) -> Result<T, Error> {
+    from_slice_with_options(context, slice, DecoderOptions::default())
+}
+
+/// Decode an instance of `T` from the given TBON-encoded slice, without an async runtime,
+/// enforcing the limits set in the given `options`.
+///
+/// The slice is copied into a new buffer before decoding. Use [`from_bytes_with_options`]
+/// to decode a [`Bytes`] buffer without copying it.
+pub fn from_slice_with_options<T: FromStream>(
+    context: T::Context,
+    slice: &[u8],
+    options: DecoderOptions,
+) -> Result<T, Error> {
+    from_bytes_with_options(context, Bytes::copy_from_slice(slice), options)
+}
+
+/// Decode an instance of `T` from the given TBON-encoded buffer, without an async runtime.
+///
+/// Unlike [`from_slice`], this does not copy the buffer, so an unescaped string or byte array
+/// can be decoded as a slice of it.
+pub fn from_bytes<T: FromStream>(context: T::Context, bytes: Bytes) -> Result<T, Error> {
+    from_bytes_with_options(context, bytes, DecoderOptions::default())
+}
+
+/// Decode an instance of `T` from the given TBON-encoded buffer, without an async runtime,
+/// enforcing the limits set in the given `options`.
+pub fn from_bytes_with_options<T: FromStream>(
+    context: T::Context,
+    bytes: Bytes,
+    options: DecoderOptions,
+) -> Result<T, Error> {
+    let source = futures::stream::once(future::ready(bytes));
+    block_on(decode_with_options(context, source, options))
+}
+
+/// Decode an instance of `T` from the given blocking [`io::Read`] source,
+/// without an async runtime.
+pub fn from_reader<R: io::Read + Send + Unpin, T: FromStream>(
+    context: T::Context,
+    reader: R,
+) -> Result<T, Error> {
+    from_reader_with_options(context, reader, DecoderOptions::default())
+}
+
+/// Decode an instance of `T` from the given blocking [`io::Read`] source,
+/// without an async runtime, enforcing the limits set in the given `options`.
+pub fn from_reader_with_options<R: io::Read + Send + Unpin, T: FromStream>(
+    context: T::Context,
+    reader: R,
+    options: DecoderOptions,
+) -> Result<T, Error> {
+    let mut decoder = Decoder::from_blocking_reader_with_options(reader, options);
+    block_on(decode_value(context, &mut decoder))
+}
+
+/// Decode an instance of `T` from the given `decoder`, after its magic header, if any.
+async fn decode_value<R: Read, T: FromStream>(
+    context: T::Context,
+    decoder: &mut Decoder<R>,
+) -> Result<T, Error> {
+    decoder.expect_header().await?;
+
+    let offset = decoder.offset();
+    T::from_stream(context, decoder)
+        .await
+        .map_err(|e| e.at(offset))
+}
@@ -0,0 +1,639 @@
+//! Inspect an encoded TBON payload, for debugging.
+//!
+//! Example:
+//! ```
+//! let encoded = tbon::to_vec(("hello", vec![1u8, 2])).unwrap();
+//! print!("{}", tbon::debug::dump(&encoded));
+//! assert!(tbon::debug::validate(&encoded).is_ok());
+//! ```
+//!
+//! Each line of a dump begins with the byte offset where the item it describes begins.
+//! Invalid or truncated input is rendered up to the point where parsing stopped, which is
+//! marked with a line beginning with `!!`.
+//!
+//! A payload may consist of any number of consecutive values, e.g. a stream of messages.
+
+use std::collections::BTreeMap;
+use std::fmt;
+
+use num_traits::FromPrimitive;
+use uuid::Uuid;
+
+use super::constants::*;
+use super::element::Element;
+use super::varint;
+
+const PREVIEW_LEN: usize = 8;
+
+/// The reason that parsing an invalid TBON payload stopped
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Invalid {
+    offset: usize,
+    message: String,
+}
+
+impl Invalid {
+    fn new<M: fmt::Display>(offset: usize, message: M) -> Self {
+        Self {
+            offset,
+            message: message.to_string(),
+        }
+    }
+
+    /// The offset, in bytes, of the item which could not be parsed.
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+}
+
+impl std::error::Error for Invalid {}
+
+impl fmt::Display for Invalid {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} at byte {}", self.message, self.offset)
+    }
+}
+
+/// Summary statistics of an encoded TBON payload
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct Stats {
+    /// The total length of the payload, in bytes
+    pub bytes: usize,
+    /// The number of scalar values of each type, by type name
+    pub scalars: BTreeMap<&'static str, usize>,
+    /// The number of typed arrays of each element type, by type name
+    pub arrays: BTreeMap<&'static str, usize>,
+    /// The total number of elements in typed arrays of each element type, by type name
+    pub array_elements: BTreeMap<&'static str, usize>,
+    /// The number of strings
+    pub strings: usize,
+    /// The number of lists
+    pub lists: usize,
+    /// The number of maps
+    pub maps: usize,
+    /// The maximum number of nested lists and maps
+    pub max_depth: usize,
+    /// The number of escape bytes in strings and typed arrays
+    pub escapes: usize,
+}
+
+/// Render the given `encoded` TBON as an indented tree.
+pub fn dump(encoded: &[u8]) -> String {
+    let mut dump = String::new();
+    write_dump(encoded, &mut dump).expect("dump");
+    dump
+}
+
+/// Write an indented tree of the given `encoded` TBON to the given `output`.
+pub fn write_dump<W: fmt::Write>(encoded: &[u8], output: &mut W) -> fmt::Result {
+    let mut format_error = None;
+    let mut line = |offset: usize, depth: usize, line: fmt::Arguments| {
+        if format_error.is_none() {
+            let indent = depth * 2;
+            if let Err(cause) = writeln!(output, "{:>8}  {:indent$}{}", offset, "", line) {
+                format_error = Some(cause);
+            }
+        }
+    };
+
+    let result = walk(encoded, |event| match event {
+        Event::Begin {
+            offset,
+            depth,
+            label,
+            is_map,
+        } => {
+            let kind = if is_map { "map" } else { "list" };
+            line(offset, depth, format_args!("{}{}", label, kind));
+        }
+        Event::End {
+            offset,
+            depth,
+            is_map,
+            len,
+        } => {
+            if is_map {
+                line(offset, depth, format_args!("end map ({} entries)", len / 2));
+            } else {
+                line(offset, depth, format_args!("end list ({} items)", len));
+            }
+        }
+        Event::Scalar {
+            offset,
+            depth,
+            label,
+            dtype: Type::None,
+            ..
+        } => line(offset, depth, format_args!("{}none", label)),
+        Event::Scalar {
+            offset,
+            depth,
+            label,
+            dtype,
+            bytes,
+        } => {
+            let value = format_value(&dtype, bytes);
+            line(offset, depth, format_args!("{}{} {}", label, dtype, value));
+        }
+        Event::String {
+            offset,
+            depth,
+            label,
+            bytes,
+            ..
+        } => {
+            let s = match String::from_utf8(bytes) {
+                Ok(s) => format!("{:?}", s),
+                Err(cause) => {
+                    let s = String::from_utf8_lossy(cause.as_bytes());
+                    format!("{:?} (invalid UTF-8)", s)
+                }
+            };
+
+            line(offset, depth, format_args!("{}string {}", label, s));
+        }
+        Event::Array {
+            offset,
+            depth,
+            label,
+            dtype,
+            bytes,
+            ..
+        } => {
+            let size = dtype.size();
+            let len = bytes.len() / size;
+            let mut preview = bytes
+                .chunks(size)
+                .take(PREVIEW_LEN)
+                .map(|element| format_value(&dtype, element))
+                .collect::<Vec<_>>()
+                .join(", ");
+
+            if len > PREVIEW_LEN {
+                preview.push_str(", ...");
+            }
+
+            let line_args = format_args!(
+                "{}array of {} ({} elements) [{}]",
+                label, dtype, len, preview
+            );
+
+            line(offset, depth, line_args);
+        }
+    });
+
+    if let Err((depth, invalid)) = result {
+        let message = format_args!("!! {} (parsing stopped here)", invalid.message);
+        line(invalid.offset, depth, message);
+    }
+
+    match format_error {
+        Some(cause) => Err(cause),
+        None => Ok(()),
+    }
+}
+
+/// Check that the given `encoded` TBON is well-formed, returning the first error found.
+///
+/// In addition to the grammar of the payload, this checks that every string is valid UTF-8.
+/// It does not check the limits set in a [`crate::de::DecoderOptions`].
+pub fn validate(encoded: &[u8]) -> Result<(), Invalid> {
+    let mut invalid = None;
+
+    let result = walk(encoded, |event| {
+        if let Event::String { offset, bytes, .. } = event {
+            if invalid.is_none() {
+                if let Err(cause) = std::str::from_utf8(&bytes) {
+                    invalid = Some(Invalid::new(offset, format!("invalid UTF-8: {}", cause)));
+                }
+            }
+        }
+    });
+
+    // an invalid string always comes before the point where parsing stopped
+    match invalid {
+        Some(invalid) => Err(invalid),
+        None => result.map_err(|(_, invalid)| invalid),
+    }
+}
+
+/// Compute summary [`Stats`] of the given `encoded` TBON.
+pub fn stats(encoded: &[u8]) -> Result<Stats, Invalid> {
+    let mut stats = Stats {
+        bytes: encoded.len(),
+        ..Stats::default()
+    };
+
+    walk(encoded, |event| match event {
+        Event::Begin { depth, is_map, .. } => {
+            if is_map {
+                stats.maps += 1;
+            } else {
+                stats.lists += 1;
+            }
+
+            stats.max_depth = Ord::max(stats.max_depth, depth + 1);
+        }
+        Event::End { .. } => {}
+        Event::Scalar { dtype, .. } => {
+            *stats.scalars.entry(dtype.name()).or_default() += 1;
+        }
+        Event::String { escapes, .. } => {
+            stats.strings += 1;
+            stats.escapes += escapes;
+        }
+        Event::Array {
+            dtype,
+            bytes,
+            escapes,
+            ..
+        } => {
+            *stats.arrays.entry(dtype.name()).or_default() += 1;
+            *stats.array_elements.entry(dtype.name()).or_default() += bytes.len() / dtype.size();
+            stats.escapes += escapes;
+        }
+    })
+    .map_err(|(_, invalid)| invalid)?;
+
+    Ok(stats)
+}
+
+/// An item parsed from an encoded TBON payload
+enum Event<'a> {
+    Begin {
+        offset: usize,
+        depth: usize,
+        label: &'static str,
+        is_map: bool,
+    },
+    End {
+        offset: usize,
+        depth: usize,
+        is_map: bool,
+        len: usize,
+    },
+    Scalar {
+        offset: usize,
+        depth: usize,
+        label: &'static str,
+        dtype: Type,
+        bytes: &'a [u8],
+    },
+    String {
+        offset: usize,
+        depth: usize,
+        label: &'static str,
+        bytes: Vec<u8>,
+        escapes: usize,
+    },
+    Array {
+        offset: usize,
+        depth: usize,
+        label: &'static str,
+        dtype: Type,
+        bytes: Vec<u8>,
+        escapes: usize,
+    },
+}
+
+/// A list or map which has begun but not yet ended
+struct Open {
+    offset: usize,
+    is_map: bool,
+    len: usize,
+}
+
+/// Parse each item in the given `encoded` TBON, in order, and pass it to `on_event`.
+///
+/// On error, returns the nesting depth where parsing stopped along with the reason.
+fn walk<'a, F>(encoded: &'a [u8], mut on_event: F) -> Result<(), (usize, Invalid)>
+where
+    F: FnMut(Event<'a>),
+{
+    let mut open: Vec<Open> = Vec::new();
+    let mut offset = 0;
+
+    while offset < encoded.len() {
+        let start = offset;
+        let byte = encoded[start];
+
+        if &[byte] == LIST_END || &[byte] == MAP_END {
+            let is_map = &[byte] == MAP_END;
+
+            match open.last() {
+                Some(container) if container.is_map == is_map => {
+                    if is_map && container.len % 2 == 1 {
+                        let invalid = Invalid::new(start, "map key without a value");
+                        return Err((open.len(), invalid));
+                    }
+                }
+                _ => {
+                    let message = format!("unexpected delimiter {:?}", byte as char);
+                    return Err((open.len(), Invalid::new(start, message)));
+                }
+            }
+
+            let container = open.pop().expect("container");
+            on_event(Event::End {
+                offset: start,
+                depth: open.len(),
+                is_map,
+                len: container.len,
+            });
+
+            offset += 1;
+            continue;
+        }
+
+        let label = match open.last_mut() {
+            Some(container) if container.is_map => {
+                container.len += 1;
+                if container.len % 2 == 1 {
+                    "key: "
+                } else {
+                    "value: "
+                }
+            }
+            Some(container) => {
+                container.len += 1;
+                ""
+            }
+            None => "",
+        };
+
+        let depth = open.len();
+        let stop = |invalid: Invalid| (depth, invalid);
+
+        match &[byte] {
+            LIST_BEGIN | MAP_BEGIN => {
+                let is_map = &[byte] == MAP_BEGIN;
+
+                on_event(Event::Begin {
+                    offset: start,
+                    depth,
+                    label,
+                    is_map,
+                });
+
+                open.push(Open {
+                    offset: start,
+                    is_map,
+                    len: 0,
+                });
+
+                offset += 1;
+            }
+            STRING_DELIMIT => {
+                let (bytes, escapes, end) =
+                    unescape(encoded, start, start + 1, STRING_DELIMIT[0], "string")
+                        .map_err(stop)?;
+
+                on_event(Event::String {
+                    offset: start,
+                    depth,
+                    label,
+                    bytes,
+                    escapes,
+                });
+
+                offset = end;
+            }
+            ARRAY_DELIMIT => {
+                let dtype = dtype(encoded, start + 1).map_err(stop)?;
+                let (bytes, escapes, end) =
+                    unescape(encoded, start, start + 2, ARRAY_DELIMIT[0], "array").map_err(stop)?;
+
+                let size = dtype.size();
+                if size == 0 || bytes.len() % size != 0 {
+                    let message = format!("{} bytes is not an array of {}", bytes.len(), dtype);
+                    return Err(stop(Invalid::new(start, message)));
+                }
+
+                on_event(Event::Array {
+                    offset: start,
+                    depth,
+                    label,
+                    dtype,
+                    bytes,
+                    escapes,
+                });
+
+                offset = end;
+            }
+            ARRAY_PREFIXED => {
+                let dtype = dtype(encoded, start + 1).map_err(stop)?;
+                let (bytes, end) = chunks(encoded, start, start + 2, &dtype).map_err(stop)?;
+
+                on_event(Event::Array {
+                    offset: start,
+                    depth,
+                    label,
+                    dtype,
+                    bytes,
+                    escapes: 0,
+                });
+
+                offset = end;
+            }
+            _ => {
+                let dtype = dtype(encoded, start).map_err(stop)?;
+                let size = match dtype {
+                    Type::VarInt | Type::VarUInt => {
+                        varint_len(encoded, start, &dtype).map_err(stop)?
+                    }
+                    _ => dtype.size(),
+                };
+
+                if start + 1 + size > encoded.len() {
+                    let message = format!(
+                        "unexpected end of input in {} ({} of {} bytes)",
+                        dtype,
+                        encoded.len() - start - 1,
+                        size
+                    );
+
+                    return Err(stop(Invalid::new(start, message)));
+                }
+
+                offset = start + 1 + size;
+
+                on_event(Event::Scalar {
+                    offset: start,
+                    depth,
+                    label,
+                    dtype,
+                    bytes: &encoded[start + 1..offset],
+                });
+            }
+        }
+    }
+
+    match open.last() {
+        Some(container) => {
+            let kind = if container.is_map { "map" } else { "list" };
+            let message = format!(
+                "unexpected end of input in {} at {}",
+                kind, container.offset
+            );
+            Err((open.len(), Invalid::new(offset, message)))
+        }
+        None => Ok(()),
+    }
+}
+
+/// Parse the type bit at the given `offset`.
+fn dtype(encoded: &[u8], offset: usize) -> Result<Type, Invalid> {
+    match encoded.get(offset) {
+        Some(bit) => match Type::from_u8(*bit) {
+            Some(dtype) => Ok(dtype),
+            None => Err(Invalid::new(
+                offset,
+                format!("unknown type bit {:#04x}", bit),
+            )),
+        },
+        None => Err(Invalid::new(offset, "unexpected end of input")),
+    }
+}
+
+/// Unescape the bytes from `start` up to the given `end` delimiter of the item which begins at
+/// `item`, returning the unescaped bytes, the number of escape bytes, and the offset following
+/// the end delimiter.
+fn unescape(
+    encoded: &[u8],
+    item: usize,
+    start: usize,
+    end: u8,
+    kind: &str,
+) -> Result<(Vec<u8>, usize, usize), Invalid> {
+    let mut unescaped = Vec::new();
+    let mut escapes = 0;
+    let mut i = start;
+
+    while i < encoded.len() {
+        match encoded[i] {
+            byte if byte == ESCAPE[0] => match encoded.get(i + 1) {
+                Some(byte) => {
+                    unescaped.push(*byte);
+                    escapes += 1;
+                    i += 2;
+                }
+                None => break,
+            },
+            byte if byte == end => return Ok((unescaped, escapes, i + 1)),
+            byte => {
+                unescaped.push(byte);
+                i += 1;
+            }
+        }
+    }
+
+    let message = format!(
+        "unexpected end of input in {} ({} bytes)",
+        kind,
+        unescaped.len()
+    );
+
+    Err(Invalid::new(item, message))
+}
+
+/// Return the length of the variable-length integer of the given `dtype` which begins at `item`,
+/// not including its type bit.
+fn varint_len(encoded: &[u8], item: usize, dtype: &Type) -> Result<usize, Invalid> {
+    let bytes = &encoded[item + 1..];
+
+    match varint::len(bytes) {
+        Some(len) if varint::decode(&bytes[..len]).is_some() => Ok(len),
+        None if bytes.len() < varint::MAX_LEN => {
+            let message = format!("unexpected end of input in {}", dtype);
+            Err(Invalid::new(item, message))
+        }
+        _ => Err(Invalid::new(item, format!("{} overflow", dtype))),
+    }
+}
+
+/// Concatenate the length-prefixed chunks of elements of the given `dtype` from `start` up to the
+/// empty chunk which ends the array which begins at `item`, returning the concatenated bytes and
+/// the offset following the end of the array.
+fn chunks(
+    encoded: &[u8],
+    item: usize,
+    start: usize,
+    dtype: &Type,
+) -> Result<(Vec<u8>, usize), Invalid> {
+    let size = dtype.size();
+    if size == 0 {
+        return Err(Invalid::new(
+            item,
+            format!("{} is not an array type", dtype),
+        ));
+    }
+
+    let mut bytes = Vec::new();
+    let mut i = start;
+
+    loop {
+        let overflow = || Invalid::new(i, "array chunk length overflow");
+
+        let varint_len = match varint::len(&encoded[i..]) {
+            Some(varint_len) => varint_len,
+            None if encoded.len() - i >= varint::MAX_LEN => return Err(overflow()),
+            None => {
+                let message = format!("unexpected end of input in array ({} bytes)", bytes.len());
+                return Err(Invalid::new(item, message));
+            }
+        };
+
+        let len = varint::decode(&encoded[i..i + varint_len])
+            .and_then(|len| usize::try_from(len).ok())
+            .and_then(|len| len.checked_mul(size))
+            .ok_or_else(overflow)?;
+
+        i += varint_len;
+
+        if len == 0 {
+            return Ok((bytes, i));
+        } else if encoded.len() - i < len {
+            let message = format!(
+                "unexpected end of input in array ({} bytes)",
+                bytes.len() + encoded.len() - i
+            );
+
+            return Err(Invalid::new(item, message));
+        }
+
+        bytes.extend_from_slice(&encoded[i..i + len]);
+        i += len;
+    }
+}
+
+fn format_value(dtype: &Type, bytes: &[u8]) -> String {
+    match dtype {
+        Type::None => "None".to_string(),
+        Type::Bool => match bytes[0] {
+            0 => "false".to_string(),
+            1 => "true".to_string(),
+            other => format!("{:#04x} (invalid boolean)", other),
+        },
+        Type::F32 => f32::from_bytes(bytes).to_string(),
+        Type::F64 => f64::from_bytes(bytes).to_string(),
+        Type::I8 => i8::from_bytes(bytes).to_string(),
+        Type::I16 => i16::from_bytes(bytes).to_string(),
+        Type::I32 => i32::from_bytes(bytes).to_string(),
+        Type::I64 => i64::from_bytes(bytes).to_string(),
+        Type::U8 => u8::from_bytes(bytes).to_string(),
+        Type::U16 => u16::from_bytes(bytes).to_string(),
+        Type::U32 => u32::from_bytes(bytes).to_string(),
+        Type::U64 => u64::from_bytes(bytes).to_string(),
+        Type::I128 => i128::from_bytes(bytes).to_string(),
+        Type::U128 => u128::from_bytes(bytes).to_string(),
+        Type::Uuid => <Uuid as Element>::from_bytes(bytes).to_string(),
+        Type::VarInt => varint::unzigzag(varint::decode(bytes).expect("varint")).to_string(),
+        Type::VarUInt => varint::decode(bytes).expect("varint").to_string(),
+        #[cfg(feature = "half")]
+        Type::F16 => half::f16::from_bytes(bytes).to_string(),
+        #[cfg(feature = "half")]
+        Type::BF16 => half::bf16::from_bytes(bytes).to_string(),
+        #[cfg(not(feature = "half"))]
+        Type::F16 | Type::BF16 => format!("{:#06x}", u16::from_bytes(bytes)),
+    }
+}
@@ -1,6 +1,7 @@
 use std::convert::TryInto;
 
 use destream::de;
+use uuid::Uuid;
 
 use super::constants::Type;
 
@@ -14,6 +15,20 @@ pub trait Element: Sized {
     // TODO: use const generic Self::SIZE to return an array
     // fn to_bytes(&self) -> [u8; Self::SIZE];
 
+    /// Convert a signed variable-length integer to this type, if this is an integer type
+    /// which can hold the value `n`.
+    #[inline]
+    fn from_varint(_n: i128) -> Option<Self> {
+        None
+    }
+
+    /// Convert an unsigned variable-length integer to this type, if this is an integer type
+    /// which can hold the value `n`.
+    #[inline]
+    fn from_unsigned_varint(_n: u128) -> Option<Self> {
+        None
+    }
+
     #[inline]
     fn parse<E: de::Error>(bytes: &[u8]) -> Result<Self, E> {
         if bytes.len() == Self::SIZE {
@@ -66,6 +81,16 @@ impl Element for u8 {
     fn from_bytes(bytes: &[u8]) -> Self {
         bytes[0]
     }
+
+    #[inline]
+    fn from_varint(n: i128) -> Option<Self> {
+        n.try_into().ok()
+    }
+
+    #[inline]
+    fn from_unsigned_varint(n: u128) -> Option<Self> {
+        n.try_into().ok()
+    }
 }
 
 impl IntoBytes<1> for u8 {
@@ -85,6 +110,16 @@ impl Element for u16 {
     fn from_bytes(bytes: &[u8]) -> Self {
         Self::from_be_bytes(bytes.try_into().unwrap())
     }
+
+    #[inline]
+    fn from_varint(n: i128) -> Option<Self> {
+        n.try_into().ok()
+    }
+
+    #[inline]
+    fn from_unsigned_varint(n: u128) -> Option<Self> {
+        n.try_into().ok()
+    }
 }
 
 impl IntoBytes<2> for u16 {
@@ -104,6 +139,16 @@ impl Element for u32 {
     fn from_bytes(bytes: &[u8]) -> Self {
         Self::from_be_bytes(bytes.try_into().unwrap())
     }
+
+    #[inline]
+    fn from_varint(n: i128) -> Option<Self> {
+        n.try_into().ok()
+    }
+
+    #[inline]
+    fn from_unsigned_varint(n: u128) -> Option<Self> {
+        n.try_into().ok()
+    }
 }
 
 impl IntoBytes<4> for u32 {
@@ -123,6 +168,16 @@ impl Element for u64 {
     fn from_bytes(bytes: &[u8]) -> Self {
         Self::from_be_bytes(bytes.try_into().unwrap())
     }
+
+    #[inline]
+    fn from_varint(n: i128) -> Option<Self> {
+        n.try_into().ok()
+    }
+
+    #[inline]
+    fn from_unsigned_varint(n: u128) -> Option<Self> {
+        n.try_into().ok()
+    }
 }
 
 impl IntoBytes<8> for u64 {
@@ -142,6 +197,16 @@ impl Element for i8 {
     fn from_bytes(bytes: &[u8]) -> Self {
         Self::from_be_bytes(bytes.try_into().unwrap())
     }
+
+    #[inline]
+    fn from_varint(n: i128) -> Option<Self> {
+        n.try_into().ok()
+    }
+
+    #[inline]
+    fn from_unsigned_varint(n: u128) -> Option<Self> {
+        n.try_into().ok()
+    }
 }
 
 impl IntoBytes<1> for i8 {
@@ -161,6 +226,16 @@ impl Element for i16 {
     fn from_bytes(bytes: &[u8]) -> Self {
         Self::from_be_bytes(bytes.try_into().unwrap())
     }
+
+    #[inline]
+    fn from_varint(n: i128) -> Option<Self> {
+        n.try_into().ok()
+    }
+
+    #[inline]
+    fn from_unsigned_varint(n: u128) -> Option<Self> {
+        n.try_into().ok()
+    }
 }
 
 impl IntoBytes<2> for i16 {
@@ -180,6 +255,16 @@ impl Element for i32 {
     fn from_bytes(bytes: &[u8]) -> Self {
         Self::from_be_bytes(bytes.try_into().unwrap())
     }
+
+    #[inline]
+    fn from_varint(n: i128) -> Option<Self> {
+        n.try_into().ok()
+    }
+
+    #[inline]
+    fn from_unsigned_varint(n: u128) -> Option<Self> {
+        n.try_into().ok()
+    }
 }
 
 impl IntoBytes<4> for i32 {
@@ -199,6 +284,16 @@ impl Element for i64 {
     fn from_bytes(bytes: &[u8]) -> Self {
         Self::from_be_bytes(bytes.try_into().unwrap())
     }
+
+    #[inline]
+    fn from_varint(n: i128) -> Option<Self> {
+        n.try_into().ok()
+    }
+
+    #[inline]
+    fn from_unsigned_varint(n: u128) -> Option<Self> {
+        n.try_into().ok()
+    }
 }
 
 impl IntoBytes<8> for i64 {
@@ -207,6 +302,83 @@ impl IntoBytes<8> for i64 {
     }
 }
 
+impl Element for i128 {
+    const SIZE: usize = 16;
+
+    fn dtype() -> Type {
+        Type::I128
+    }
+
+    #[inline]
+    fn from_bytes(bytes: &[u8]) -> Self {
+        Self::from_be_bytes(bytes.try_into().unwrap())
+    }
+
+    #[inline]
+    fn from_varint(n: i128) -> Option<Self> {
+        Some(n)
+    }
+
+    #[inline]
+    fn from_unsigned_varint(n: u128) -> Option<Self> {
+        n.try_into().ok()
+    }
+}
+
+impl IntoBytes<16> for i128 {
+    fn into_bytes(self) -> [u8; 16] {
+        self.to_be_bytes()
+    }
+}
+
+impl Element for u128 {
+    const SIZE: usize = 16;
+
+    fn dtype() -> Type {
+        Type::U128
+    }
+
+    #[inline]
+    fn from_bytes(bytes: &[u8]) -> Self {
+        Self::from_be_bytes(bytes.try_into().unwrap())
+    }
+
+    #[inline]
+    fn from_varint(n: i128) -> Option<Self> {
+        n.try_into().ok()
+    }
+
+    #[inline]
+    fn from_unsigned_varint(n: u128) -> Option<Self> {
+        Some(n)
+    }
+}
+
+impl IntoBytes<16> for u128 {
+    fn into_bytes(self) -> [u8; 16] {
+        self.to_be_bytes()
+    }
+}
+
+impl Element for Uuid {
+    const SIZE: usize = 16;
+
+    fn dtype() -> Type {
+        Type::Uuid
+    }
+
+    #[inline]
+    fn from_bytes(bytes: &[u8]) -> Self {
+        Self::from_bytes(bytes.try_into().unwrap())
+    }
+}
+
+impl IntoBytes<16> for Uuid {
+    fn into_bytes(self) -> [u8; 16] {
+        self.into_bytes()
+    }
+}
+
 impl Element for f32 {
     const SIZE: usize = 4;
 
@@ -244,3 +416,45 @@ impl IntoBytes<8> for f64 {
         self.to_be_bytes()
     }
 }
+
+#[cfg(feature = "half")]
+impl Element for half::f16 {
+    const SIZE: usize = 2;
+
+    fn dtype() -> Type {
+        Type::F16
+    }
+
+    #[inline]
+    fn from_bytes(bytes: &[u8]) -> Self {
+        Self::from_be_bytes(bytes.try_into().unwrap())
+    }
+}
+
+#[cfg(feature = "half")]
+impl IntoBytes<2> for half::f16 {
+    fn into_bytes(self) -> [u8; 2] {
+        self.to_be_bytes()
+    }
+}
+
+#[cfg(feature = "half")]
+impl Element for half::bf16 {
+    const SIZE: usize = 2;
+
+    fn dtype() -> Type {
+        Type::BF16
+    }
+
+    #[inline]
+    fn from_bytes(bytes: &[u8]) -> Self {
+        Self::from_be_bytes(bytes.try_into().unwrap())
+    }
+}
+
+#[cfg(feature = "half")]
+impl IntoBytes<2> for half::bf16 {
+    fn into_bytes(self) -> [u8; 2] {
+        self.to_be_bytes()
+    }
+}
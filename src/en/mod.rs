@@ -2,20 +2,34 @@
 
 use std::collections::VecDeque;
 use std::fmt;
+use std::io;
+use std::marker::PhantomData;
 use std::mem;
 use std::pin::Pin;
 
 use bytes::{BufMut, Bytes, BytesMut};
 use destream::{en, IntoStream};
+use futures::executor::block_on_stream;
 use futures::future;
 use futures::stream::{Stream, StreamExt};
+use memchr::{memchr2_iter, memchr3_iter};
 use num_traits::ToPrimitive;
 use uuid::Uuid;
 
+#[cfg(feature = "tokio-io")]
+use tokio::io::AsyncWrite;
+
+#[cfg(any(feature = "tokio-io", feature = "futures-io"))]
+pub use write::WriteOptions;
+
 use super::constants::*;
 use super::element::{Element, IntoBytes};
+use super::hook;
+use super::varint;
 
 mod stream;
+#[cfg(any(feature = "tokio-io", feature = "futures-io"))]
+mod write;
 
 /// A [`Stream`] of [`Bytes`] chunks
 pub type ByteStream<'en> = Pin<Box<dyn Stream<Item = Result<Bytes, Error>> + Send + Unpin + 'en>>;
@@ -25,6 +39,12 @@ pub struct Error {
     message: String,
 }
 
+impl Error {
+    fn io<I: fmt::Display>(cause: I) -> Self {
+        en::Error::custom(format!("io error: {}", cause))
+    }
+}
+
 impl en::Error for Error {
     fn custom<I: fmt::Display>(info: I) -> Self {
         Self {
@@ -35,6 +55,12 @@ impl en::Error for Error {
 
 impl std::error::Error for Error {}
 
+impl From<io::Error> for Error {
+    fn from(cause: io::Error) -> Self {
+        Self::io(cause)
+    }
+}
+
 impl fmt::Debug for Error {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         fmt::Display::fmt(self, f)
@@ -49,13 +75,14 @@ impl fmt::Display for Error {
 
 /// An [`Encoder`] for a map of keys to values
 pub struct MapEncoder<'en> {
+    encoder: Encoder,
     pending_key: Option<ByteStream<'en>>,
     entries: VecDeque<(ByteStream<'en>, ByteStream<'en>)>,
 }
 
 impl<'en> MapEncoder<'en> {
     #[inline]
-    fn new(size_hint: Option<usize>) -> Self {
+    fn new(encoder: Encoder, size_hint: Option<usize>) -> Self {
         let entries = if let Some(len) = size_hint {
             VecDeque::with_capacity(len)
         } else {
@@ -63,6 +90,7 @@ impl<'en> MapEncoder<'en> {
         };
 
         Self {
+            encoder,
             pending_key: None,
             entries,
         }
@@ -76,7 +104,7 @@ impl<'en> en::EncodeMap<'en> for MapEncoder<'en> {
     #[inline]
     fn encode_key<T: en::IntoStream<'en> + 'en>(&mut self, key: T) -> Result<(), Self::Error> {
         if self.pending_key.is_none() {
-            self.pending_key = Some(key.into_stream(Encoder)?);
+            self.pending_key = Some(key.into_stream(self.encoder)?);
             Ok(())
         } else {
             Err(en::Error::custom(
@@ -93,7 +121,7 @@ impl<'en> en::EncodeMap<'en> for MapEncoder<'en> {
             ));
         }
 
-        let value = value.into_stream(Encoder)?;
+        let value = value.into_stream(self.encoder)?;
 
         let mut key = None;
         mem::swap(&mut self.pending_key, &mut key);
@@ -102,39 +130,46 @@ impl<'en> en::EncodeMap<'en> for MapEncoder<'en> {
         Ok(())
     }
 
-    fn end(mut self) -> Result<Self::Ok, Self::Error> {
+    fn end(self) -> Result<Self::Ok, Self::Error> {
         if self.pending_key.is_some() {
             return Err(en::Error::custom(
                 "You must call encode_value after calling encode_key",
             ));
         }
 
-        let mut encoded = delimiter(MAP_BEGIN);
-
-        while let Some((key, value)) = self.entries.pop_front() {
-            encoded = Box::pin(encoded.chain(key).chain(value));
+        if self.encoder.options.canonical_maps {
+            let entries = futures::stream::iter(self.entries.into_iter().map(Ok));
+            return Ok(stream::encode_canonical_map(entries));
         }
 
-        encoded = Box::pin(encoded.chain(delimiter(MAP_END)));
-        Ok(encoded)
+        let items = self
+            .entries
+            .into_iter()
+            .flat_map(|(key, value)| [key, value])
+            .collect();
+
+        Ok(Box::pin(stream::DelimitedStream::new(
+            MAP_BEGIN, items, MAP_END,
+        )))
     }
 }
 
 /// An [`Encoder`] for a sequence of values
 pub struct SequenceEncoder<'en> {
+    encoder: Encoder,
     items: VecDeque<ByteStream<'en>>,
 }
 
 impl<'en> SequenceEncoder<'en> {
     #[inline]
-    fn new(size_hint: Option<usize>) -> Self {
+    fn new(encoder: Encoder, size_hint: Option<usize>) -> Self {
         let items = if let Some(len) = size_hint {
             VecDeque::with_capacity(len)
         } else {
             VecDeque::new()
         };
 
-        Self { items }
+        Self { encoder, items }
     }
 
     #[inline]
@@ -142,15 +177,10 @@ impl<'en> SequenceEncoder<'en> {
         self.items.push_back(value);
     }
 
-    fn encode(mut self) -> Result<ByteStream<'en>, Error> {
-        let mut encoded = delimiter(LIST_BEGIN);
-
-        while let Some(item) = self.items.pop_front() {
-            encoded = Box::pin(encoded.chain(item));
-        }
-
-        encoded = Box::pin(encoded.chain(delimiter(LIST_END)));
-        Ok(encoded)
+    fn encode(self) -> Result<ByteStream<'en>, Error> {
+        Ok(Box::pin(stream::DelimitedStream::new(
+            LIST_BEGIN, self.items, LIST_END,
+        )))
     }
 }
 
@@ -163,7 +193,7 @@ impl<'en> en::EncodeSeq<'en> for SequenceEncoder<'en> {
         &mut self,
         value: T,
     ) -> Result<(), Self::Error> {
-        let encoded = value.into_stream(Encoder)?;
+        let encoded = value.into_stream(self.encoder)?;
         self.push(encoded);
         Ok(())
     }
@@ -182,7 +212,7 @@ impl<'en> en::EncodeTuple<'en> for SequenceEncoder<'en> {
         &mut self,
         value: T,
     ) -> Result<(), Self::Error> {
-        let encoded = value.into_stream(Encoder)?;
+        let encoded = value.into_stream(self.encoder)?;
         self.push(encoded);
         Ok(())
     }
@@ -192,10 +222,135 @@ impl<'en> en::EncodeTuple<'en> for SequenceEncoder<'en> {
     }
 }
 
+/// The default target size of a chunk of an encoded stream, in bytes.
+pub(crate) const CHUNK_SIZE: usize = 8192;
+
+/// Options which control how an [`Encoder`] encodes a value.
+///
+/// By default the output can be decoded by any version of TBON which supports the types encoded.
+#[derive(Clone, Copy, Debug)]
+pub struct EncoderOptions {
+    canonical_maps: bool,
+    compact_integers: bool,
+    length_prefixed_arrays: bool,
+    chunk_size: usize,
+    max_depth: usize,
+    magic_header: Option<&'static [u8]>,
+}
+
+impl Default for EncoderOptions {
+    fn default() -> Self {
+        Self {
+            canonical_maps: false,
+            compact_integers: false,
+            length_prefixed_arrays: false,
+            chunk_size: CHUNK_SIZE,
+            max_depth: usize::MAX,
+            magic_header: None,
+        }
+    }
+}
+
+impl EncoderOptions {
+    /// Construct a new set of default [`EncoderOptions`].
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Encode the entries of each map in order of their encoded keys, rather than the order
+    /// in which they're given, so that equal maps always have the same encoding,
+    /// e.g. in order to hash or sign them.
+    ///
+    /// This requires buffering each map's keys, and makes a duplicate key an error. A map given as
+    /// a stream, e.g. to [`encode_map_with_options`], is read to its end before any of it is
+    /// encoded, so all of its entries are held in memory; don't use this option to encode a map
+    /// stream which may be very long or never end.
+    pub fn canonical_maps(mut self, canonical_maps: bool) -> Self {
+        self.canonical_maps = canonical_maps;
+        self
+    }
+
+    /// Encode each integer as a variable-length integer, if that's shorter than its fixed-width
+    /// encoding, e.g. to encode a `u64` whose value is `3` in two bytes instead of nine.
+    ///
+    /// This does not apply to typed arrays. A version of TBON which predates this option cannot
+    /// decode the result.
+    pub fn compact_integers(mut self, compact_integers: bool) -> Self {
+        self.compact_integers = compact_integers;
+        self
+    }
+
+    /// Encode typed arrays as chunks of raw elements, each prefixed with its length,
+    /// rather than escaping each element which contains a control byte.
+    ///
+    /// This makes the size of an array predictable and lets the decoder copy elements without
+    /// scanning them, but a version of TBON which predates this option cannot decode the result.
+    pub fn length_prefixed_arrays(mut self, length_prefixed_arrays: bool) -> Self {
+        self.length_prefixed_arrays = length_prefixed_arrays;
+        self
+    }
+
+    /// Merge adjacent chunks of the encoded stream into chunks of about `chunk_size` bytes,
+    /// rather than emitting each delimiter and scalar value as a separate chunk.
+    ///
+    /// A chunk is never held back while the value being encoded is waiting for more input,
+    /// so this does not delay a streaming value. The default is 8 KiB, and a `chunk_size` of zero
+    /// disables this.
+    pub fn chunk_size(mut self, chunk_size: usize) -> Self {
+        self.chunk_size = chunk_size;
+        self
+    }
+
+    /// Set the maximum nesting depth of lists and maps.
+    pub fn max_depth(mut self, max_depth: usize) -> Self {
+        self.max_depth = max_depth;
+        self
+    }
+
+    /// Begin the encoded stream with the given `magic_header`, e.g. to identify its format.
+    ///
+    /// The header is written by the encoding functions in this module, e.g.
+    /// [`encode_with_options`], not by an [`Encoder`] used directly. Use
+    /// [`crate::de::DecoderOptions::magic_header`] to decode the result.
+    pub fn magic_header(mut self, magic_header: &'static [u8]) -> Self {
+        self.magic_header = Some(magic_header);
+        self
+    }
+}
+
 /// A TBON encoder
-pub struct Encoder;
+#[derive(Clone, Copy, Default)]
+pub struct Encoder {
+    options: EncoderOptions,
+    depth: usize,
+}
 
 impl Encoder {
+    /// Construct a new [`Encoder`] with the default [`EncoderOptions`].
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Construct a new [`Encoder`] with the given `options`.
+    pub fn with_options(options: EncoderOptions) -> Self {
+        Self { options, depth: 0 }
+    }
+
+    /// Return an [`Encoder`] for the contents of a list or map encoded by this [`Encoder`].
+    fn descend(self) -> Result<Self, Error> {
+        if self.depth < self.options.max_depth {
+            Ok(Self {
+                options: self.options,
+                depth: self.depth + 1,
+            })
+        } else {
+            Err(en::Error::custom(format!(
+                "limit exceeded: nesting depth is greater than {}",
+                self.options.max_depth
+            )))
+        }
+    }
+
     #[inline]
     fn encode_type<'en>(&self, dtype: &Type, value: &[u8]) -> Result<ByteStream<'en>, Error> {
         let mut chunk = BytesMut::with_capacity(value.len() + 1);
@@ -207,6 +362,47 @@ impl Encoder {
         )))))
     }
 
+    /// Encode a signed integer `n`, whose fixed-width encoding is `bytes`.
+    #[inline]
+    fn encode_signed<'en>(
+        &self,
+        dtype: &Type,
+        bytes: &[u8],
+        n: i128,
+    ) -> Result<ByteStream<'en>, Error> {
+        self.encode_int(dtype, bytes, &Type::VarInt, varint::zigzag(n))
+    }
+
+    /// Encode an unsigned integer `n`, whose fixed-width encoding is `bytes`.
+    #[inline]
+    fn encode_unsigned<'en>(
+        &self,
+        dtype: &Type,
+        bytes: &[u8],
+        n: u128,
+    ) -> Result<ByteStream<'en>, Error> {
+        self.encode_int(dtype, bytes, &Type::VarUInt, n)
+    }
+
+    /// Encode an integer as a variable-length integer of type `varint_type` with the value `n`,
+    /// if that's enabled and shorter than its fixed-width encoding `bytes`.
+    #[inline]
+    fn encode_int<'en>(
+        &self,
+        dtype: &Type,
+        bytes: &[u8],
+        varint_type: &Type,
+        n: u128,
+    ) -> Result<ByteStream<'en>, Error> {
+        if self.options.compact_integers && varint::encoded_len(n) < bytes.len() {
+            let mut encoded = Vec::with_capacity(varint::MAX_LEN);
+            varint::put(n, &mut encoded);
+            self.encode_type(varint_type, &encoded)
+        } else {
+            self.encode_type(dtype, bytes)
+        }
+    }
+
     #[inline]
     fn encode_string_type<'en>(
         &self,
@@ -216,7 +412,7 @@ impl Encoder {
     ) -> Result<ByteStream<'en>, Error> {
         let mut chunk = BytesMut::with_capacity(value.len() + 2);
         chunk.put_u8(start);
-        chunk.extend(self.escape(value, &[start, end]));
+        escape(value, &[start, end], &mut chunk);
         chunk.put_u8(end);
 
         Ok(Box::pin(futures::stream::once(future::ready(Ok(
@@ -224,17 +420,150 @@ impl Encoder {
         )))))
     }
 
-    fn escape(&self, value: &[u8], control: &[u8]) -> Vec<u8> {
-        let mut escaped = Vec::with_capacity(value.len() * 2);
-        for char in value {
-            if control.contains(char) || char == &ESCAPE[0] {
-                escaped.push(ESCAPE[0])
-            }
+    #[inline]
+    fn encode_byte_slice<'en>(&self, bytes: &[u8]) -> Result<ByteStream<'en>, Error> {
+        let mut array = BytesMut::with_capacity(bytes.len() + 3);
+
+        if self.options.length_prefixed_arrays {
+            array.extend_from_slice(ARRAY_PREFIXED);
+            array.put_u8(u8::dtype().to_u8().expect("type bit"));
+            put_chunk(bytes, 1, &mut array);
+            varint::put(0, &mut array);
+        } else {
+            array.extend_from_slice(ARRAY_DELIMIT);
+            array.put_u8(u8::dtype().to_u8().expect("type bit"));
+            escape(bytes, ARRAY_DELIMIT, &mut array);
+            array.extend_from_slice(ARRAY_DELIMIT);
+        }
+
+        let array: ByteStream = Box::pin(futures::stream::once(future::ready(Ok(array.into()))));
+        Ok(array)
+    }
 
-            escaped.push(*char);
+    /// Return a [`Hook`] to call the methods of this [`Encoder`] which [`en::Encoder`] has no
+    /// method for, like [`Encoder::encode_i128`], if the given `encoder` is a TBON [`Encoder`].
+    pub(crate) fn hook<'en, E: en::Encoder<'en>>(encoder: E) -> Result<Hook<'en, E>, E> {
+        if hook::is::<Self, E>() {
+            // SAFETY: E is an Encoder, which has no lifetime parameters
+            let encoder = unsafe { hook::cast(encoder) };
+
+            Ok(Hook {
+                encoder,
+                phantom: PhantomData,
+            })
+        } else {
+            Err(encoder)
+        }
+    }
+
+    fn encode_array<'en, const SIZE: usize, E, T, S>(
+        &self,
+        dtype: Type,
+        chunks: S,
+    ) -> ByteStream<'en>
+    where
+        E: IntoBytes<SIZE>,
+        T: IntoIterator<Item = E>,
+        S: Stream<Item = T> + Send + Unpin + 'en,
+    {
+        if self.options.length_prefixed_arrays {
+            encode_array_prefixed(dtype, chunks)
+        } else {
+            encode_array_escaped(dtype, chunks)
         }
+    }
+
+    /// Encode a 128-bit signed integer.
+    ///
+    /// [`en::Encoder`] has no methods for 128-bit integers, so they can only be encoded
+    /// by calling this [`Encoder`] directly, or as a [`crate::Value`]. The [`crate::de::Decoder`]
+    /// will decode a 128-bit integer which fits in 64 bits as an `i64` or `u64`, unless it's
+    /// decoded as a [`crate::Value`].
+    pub fn encode_i128<'en>(&self, v: i128) -> Result<ByteStream<'en>, Error> {
+        self.encode_signed(&Type::I128, &v.to_be_bytes(), v)
+    }
+
+    /// Encode a 128-bit unsigned integer. See [`Encoder::encode_i128`].
+    pub fn encode_u128<'en>(&self, v: u128) -> Result<ByteStream<'en>, Error> {
+        self.encode_unsigned(&Type::U128, &v.to_be_bytes(), v)
+    }
+
+    /// Encode an array of 128-bit signed integers. See [`Encoder::encode_i128`].
+    pub fn encode_array_i128<'en, T, S>(&self, chunks: S) -> Result<ByteStream<'en>, Error>
+    where
+        T: IntoIterator<Item = i128> + Send + Unpin + 'en,
+        S: Stream<Item = T> + Send + Unpin + 'en,
+        <T as IntoIterator>::IntoIter: Send + Unpin + 'en,
+    {
+        Ok(self.encode_array(Type::I128, chunks))
+    }
+
+    /// Encode an array of 128-bit unsigned integers. See [`Encoder::encode_i128`].
+    pub fn encode_array_u128<'en, T, S>(&self, chunks: S) -> Result<ByteStream<'en>, Error>
+    where
+        T: IntoIterator<Item = u128> + Send + Unpin + 'en,
+        S: Stream<Item = T> + Send + Unpin + 'en,
+        <T as IntoIterator>::IntoIter: Send + Unpin + 'en,
+    {
+        Ok(self.encode_array(Type::U128, chunks))
+    }
+
+    /// Encode a 16-bit float.
+    ///
+    /// [`en::Encoder`] has no methods for 16-bit floats, so they can only be encoded by calling
+    /// this [`Encoder`] directly, or as a [`crate::Value`]. The [`crate::de::Decoder`] will widen
+    /// a 16-bit float to an `f32` unless it's decoded with [`crate::de::Decoder::decode_f16`] or
+    /// as a [`crate::Value`].
+    #[cfg(feature = "half")]
+    pub fn encode_f16<'en>(&self, v: half::f16) -> Result<ByteStream<'en>, Error> {
+        self.encode_type(&Type::F16, &v.to_be_bytes())
+    }
+
+    /// Encode a 16-bit "brain" float. See [`Encoder::encode_f16`].
+    #[cfg(feature = "half")]
+    pub fn encode_bf16<'en>(&self, v: half::bf16) -> Result<ByteStream<'en>, Error> {
+        self.encode_type(&Type::BF16, &v.to_be_bytes())
+    }
+
+    /// Encode an array of 16-bit floats. See [`Encoder::encode_f16`].
+    #[cfg(feature = "half")]
+    pub fn encode_array_f16<'en, T, S>(&self, chunks: S) -> Result<ByteStream<'en>, Error>
+    where
+        T: IntoIterator<Item = half::f16> + Send + Unpin + 'en,
+        S: Stream<Item = T> + Send + Unpin + 'en,
+        <T as IntoIterator>::IntoIter: Send + Unpin + 'en,
+    {
+        Ok(self.encode_array(Type::F16, chunks))
+    }
 
-        escaped
+    /// Encode an array of 16-bit "brain" floats. See [`Encoder::encode_f16`].
+    #[cfg(feature = "half")]
+    pub fn encode_array_bf16<'en, T, S>(&self, chunks: S) -> Result<ByteStream<'en>, Error>
+    where
+        T: IntoIterator<Item = half::bf16> + Send + Unpin + 'en,
+        S: Stream<Item = T> + Send + Unpin + 'en,
+        <T as IntoIterator>::IntoIter: Send + Unpin + 'en,
+    {
+        Ok(self.encode_array(Type::BF16, chunks))
+    }
+}
+
+/// A generic [`en::Encoder`] which is a TBON [`Encoder`]
+pub(crate) struct Hook<'en, E> {
+    encoder: Encoder,
+    phantom: PhantomData<fn(&'en ()) -> E>,
+}
+
+impl<'en, E: en::Encoder<'en>> Hook<'en, E> {
+    /// Encode a value by calling the methods of the TBON [`Encoder`] directly.
+    pub(crate) fn encode<F>(self, encode: F) -> Result<E::Ok, E::Error>
+    where
+        F: FnOnce(&Encoder) -> Result<ByteStream<'en>, Error>,
+    {
+        let encoded = encode(&self.encoder);
+
+        // SAFETY: E is an Encoder, whose Ok and Error types are a ByteStream<'en> and an Error
+        unsafe { hook::cast(encoded) }
     }
 }
 
@@ -253,46 +582,46 @@ impl<'en> en::Encoder<'en> for Encoder {
 
     #[inline]
     fn encode_bytes<B: Into<Bytes>>(self, bytes: B) -> Result<Self::Ok, Self::Error> {
-        self.collect_bytes(bytes.into())
+        self.encode_byte_slice(&bytes.into())
     }
 
     #[inline]
     fn encode_i8(self, v: i8) -> Result<Self::Ok, Self::Error> {
-        self.encode_type(&Type::I8, &v.to_be_bytes())
+        self.encode_signed(&Type::I8, &v.to_be_bytes(), v.into())
     }
 
     fn encode_i16(self, v: i16) -> Result<Self::Ok, Self::Error> {
-        self.encode_type(&Type::I16, &v.to_be_bytes())
+        self.encode_signed(&Type::I16, &v.to_be_bytes(), v.into())
     }
 
     #[inline]
     fn encode_i32(self, v: i32) -> Result<Self::Ok, Self::Error> {
-        self.encode_type(&Type::I32, &v.to_be_bytes())
+        self.encode_signed(&Type::I32, &v.to_be_bytes(), v.into())
     }
 
     #[inline]
     fn encode_i64(self, v: i64) -> Result<Self::Ok, Self::Error> {
-        self.encode_type(&Type::I64, &v.to_be_bytes())
+        self.encode_signed(&Type::I64, &v.to_be_bytes(), v.into())
     }
 
     #[inline]
     fn encode_u8(self, v: u8) -> Result<Self::Ok, Self::Error> {
-        self.encode_type(&Type::U8, &v.to_be_bytes())
+        self.encode_unsigned(&Type::U8, &v.to_be_bytes(), v.into())
     }
 
     #[inline]
     fn encode_u16(self, v: u16) -> Result<Self::Ok, Self::Error> {
-        self.encode_type(&Type::U16, &v.to_be_bytes())
+        self.encode_unsigned(&Type::U16, &v.to_be_bytes(), v.into())
     }
 
     #[inline]
     fn encode_u32(self, v: u32) -> Result<Self::Ok, Self::Error> {
-        self.encode_type(&Type::U32, &v.to_be_bytes())
+        self.encode_unsigned(&Type::U32, &v.to_be_bytes(), v.into())
     }
 
     #[inline]
     fn encode_u64(self, v: u64) -> Result<Self::Ok, Self::Error> {
-        self.encode_type(&Type::U64, &v.to_be_bytes())
+        self.encode_unsigned(&Type::U64, &v.to_be_bytes(), v.into())
     }
 
     #[inline]
@@ -311,7 +640,7 @@ impl<'en> en::Encoder<'en> for Encoder {
         S: Stream<Item = T> + Send + Unpin + 'en,
         <T as IntoIterator>::IntoIter: Send + Unpin + 'en,
     {
-        Ok(encode_array(Type::Bool, chunks))
+        Ok(self.encode_array(Type::Bool, chunks))
     }
 
     fn encode_array_i8<T, S>(self, chunks: S) -> Result<Self::Ok, Self::Error>
@@ -320,7 +649,7 @@ impl<'en> en::Encoder<'en> for Encoder {
         S: Stream<Item = T> + Send + Unpin + 'en,
         <T as IntoIterator>::IntoIter: Send + Unpin + 'en,
     {
-        Ok(encode_array(Type::I8, chunks))
+        Ok(self.encode_array(Type::I8, chunks))
     }
 
     fn encode_array_i16<T, S>(self, chunks: S) -> Result<Self::Ok, Self::Error>
@@ -329,7 +658,7 @@ impl<'en> en::Encoder<'en> for Encoder {
         S: Stream<Item = T> + Send + Unpin + 'en,
         <T as IntoIterator>::IntoIter: Send + Unpin + 'en,
     {
-        Ok(encode_array(Type::I16, chunks))
+        Ok(self.encode_array(Type::I16, chunks))
     }
 
     fn encode_array_i32<T, S>(self, chunks: S) -> Result<Self::Ok, Self::Error>
@@ -338,7 +667,7 @@ impl<'en> en::Encoder<'en> for Encoder {
         S: Stream<Item = T> + Send + Unpin + 'en,
         <T as IntoIterator>::IntoIter: Send + Unpin + 'en,
     {
-        Ok(encode_array(Type::I32, chunks))
+        Ok(self.encode_array(Type::I32, chunks))
     }
 
     fn encode_array_i64<T, S>(self, chunks: S) -> Result<Self::Ok, Self::Error>
@@ -347,7 +676,7 @@ impl<'en> en::Encoder<'en> for Encoder {
         S: Stream<Item = T> + Send + Unpin + 'en,
         <T as IntoIterator>::IntoIter: Send + Unpin + 'en,
     {
-        Ok(encode_array(Type::I64, chunks))
+        Ok(self.encode_array(Type::I64, chunks))
     }
 
     fn encode_array_u8<T, S>(self, chunks: S) -> Result<Self::Ok, Self::Error>
@@ -356,7 +685,7 @@ impl<'en> en::Encoder<'en> for Encoder {
         S: Stream<Item = T> + Send + Unpin + 'en,
         <T as IntoIterator>::IntoIter: Send + Unpin + 'en,
     {
-        Ok(encode_array(Type::U8, chunks))
+        Ok(self.encode_array(Type::U8, chunks))
     }
 
     fn encode_array_u16<T, S>(self, chunks: S) -> Result<Self::Ok, Self::Error>
@@ -365,7 +694,7 @@ impl<'en> en::Encoder<'en> for Encoder {
         S: Stream<Item = T> + Send + Unpin + 'en,
         <T as IntoIterator>::IntoIter: Send + Unpin + 'en,
     {
-        Ok(encode_array(Type::U16, chunks))
+        Ok(self.encode_array(Type::U16, chunks))
     }
 
     fn encode_array_u32<T, S>(self, chunks: S) -> Result<Self::Ok, Self::Error>
@@ -374,7 +703,7 @@ impl<'en> en::Encoder<'en> for Encoder {
         S: Stream<Item = T> + Send + Unpin + 'en,
         <T as IntoIterator>::IntoIter: Send + Unpin + 'en,
     {
-        Ok(encode_array(Type::U32, chunks))
+        Ok(self.encode_array(Type::U32, chunks))
     }
 
     fn encode_array_u64<T, S>(self, chunks: S) -> Result<Self::Ok, Self::Error>
@@ -383,7 +712,7 @@ impl<'en> en::Encoder<'en> for Encoder {
         S: Stream<Item = T> + Send + Unpin + 'en,
         <T as IntoIterator>::IntoIter: Send + Unpin + 'en,
     {
-        Ok(encode_array(Type::U64, chunks))
+        Ok(self.encode_array(Type::U64, chunks))
     }
 
     fn encode_array_f32<T, S>(self, chunks: S) -> Result<Self::Ok, Self::Error>
@@ -392,7 +721,7 @@ impl<'en> en::Encoder<'en> for Encoder {
         S: Stream<Item = T> + Send + Unpin + 'en,
         <T as IntoIterator>::IntoIter: Send + Unpin + 'en,
     {
-        Ok(encode_array(Type::F32, chunks))
+        Ok(self.encode_array(Type::F32, chunks))
     }
 
     fn encode_array_f64<T, S>(self, chunks: S) -> Result<Self::Ok, Self::Error>
@@ -401,7 +730,7 @@ impl<'en> en::Encoder<'en> for Encoder {
         S: Stream<Item = T> + Send + Unpin + 'en,
         <T as IntoIterator>::IntoIter: Send + Unpin + 'en,
     {
-        Ok(encode_array(Type::F64, chunks))
+        Ok(self.encode_array(Type::F64, chunks))
     }
 
     #[inline]
@@ -428,12 +757,12 @@ impl<'en> en::Encoder<'en> for Encoder {
 
     #[inline]
     fn encode_uuid(self, uuid: Uuid) -> Result<Self::Ok, Self::Error> {
-        self.collect_bytes(uuid.as_bytes().into_iter().copied())
+        self.encode_type(&Type::Uuid, uuid.as_bytes())
     }
 
     #[inline]
     fn encode_map(self, size_hint: Option<usize>) -> Result<Self::EncodeMap, Self::Error> {
-        Ok(MapEncoder::new(size_hint))
+        Ok(MapEncoder::new(self.descend()?, size_hint))
     }
 
     #[inline]
@@ -443,12 +772,20 @@ impl<'en> en::Encoder<'en> for Encoder {
         V: en::IntoStream<'en> + 'en,
         S: Stream<Item = (K, V)> + Send + Unpin + 'en,
     {
-        Ok(Box::pin(stream::encode_map(map)))
+        let encoder = self.descend()?;
+
+        if self.options.canonical_maps {
+            Ok(stream::encode_canonical_map(map.map(
+                move |(key, value)| Ok((key.into_stream(encoder)?, value.into_stream(encoder)?)),
+            )))
+        } else {
+            Ok(Box::pin(stream::encode_map(encoder, map)))
+        }
     }
 
     #[inline]
     fn encode_seq(self, size_hint: Option<usize>) -> Result<Self::EncodeSeq, Self::Error> {
-        Ok(SequenceEncoder::new(size_hint))
+        Ok(SequenceEncoder::new(self.descend()?, size_hint))
     }
 
     #[inline]
@@ -456,39 +793,18 @@ impl<'en> en::Encoder<'en> for Encoder {
         self,
         seq: S,
     ) -> Result<Self::Ok, Self::Error> {
-        Ok(Box::pin(stream::encode_list(seq)))
+        Ok(Box::pin(stream::encode_list(self.descend()?, seq)))
     }
 
     #[inline]
     fn encode_tuple(self, len: usize) -> Result<Self::EncodeTuple, Self::Error> {
-        Ok(SequenceEncoder::new(Some(len)))
+        Ok(SequenceEncoder::new(self.descend()?, Some(len)))
     }
 
     #[inline]
     fn collect_bytes<B: IntoIterator<Item = u8>>(self, bytes: B) -> Result<Self::Ok, Self::Error> {
-        let bytes = bytes.into_iter();
-        let mut array = match bytes.size_hint() {
-            (0, None) | (0, Some(usize::MAX)) => Vec::new(),
-            (_min, Some(max)) => Vec::with_capacity(max + 3),
-            (min, None) => Vec::with_capacity(min),
-        };
-
-        array.extend_from_slice(ARRAY_DELIMIT);
-        array.push(u8::dtype().to_u8().expect("type bit"));
-
-        for byte in bytes {
-            let as_slice = std::slice::from_ref(&byte);
-            if as_slice == ARRAY_DELIMIT || as_slice == ESCAPE {
-                array.extend_from_slice(ESCAPE);
-            }
-
-            array.put_u8(byte);
-        }
-
-        array.extend_from_slice(ARRAY_DELIMIT);
-
-        let array: ByteStream = Box::pin(futures::stream::once(future::ready(Ok(array.into()))));
-        Ok(array)
+        let bytes: Vec<u8> = bytes.into_iter().collect();
+        self.encode_byte_slice(&bytes)
     }
 }
 
@@ -502,8 +818,225 @@ fn delimiter<'en>(delimiter: &'static [u8]) -> ByteStream<'en> {
 /// Given an encodable value, return an encoded stream.
 pub fn encode<'en, T: IntoStream<'en> + 'en>(
     value: T,
-) -> Result<impl Stream<Item = Result<Bytes, Error>> + 'en, Error> {
-    value.into_stream(Encoder)
+) -> Result<impl Stream<Item = Result<Bytes, Error>> + Send + Unpin + 'en, Error> {
+    encode_with_options(value, EncoderOptions::default())
+}
+
+/// Given an encodable value, return a stream encoded using the given `options`.
+pub fn encode_with_options<'en, T: IntoStream<'en> + 'en>(
+    value: T,
+    options: EncoderOptions,
+) -> Result<impl Stream<Item = Result<Bytes, Error>> + Send + Unpin + 'en, Error> {
+    let encoded = value.into_stream(Encoder::with_options(options))?;
+    Ok(finish(encoded, options))
+}
+
+/// Encode the given value into a [`Vec`] of bytes, without an async runtime.
+pub fn to_vec<'en, T: IntoStream<'en> + 'en>(value: T) -> Result<Vec<u8>, Error> {
+    to_vec_with_options(value, EncoderOptions::default())
+}
+
+/// Encode the given value into a [`Vec`] of bytes using the given `options`,
+/// without an async runtime.
+pub fn to_vec_with_options<'en, T: IntoStream<'en> + 'en>(
+    value: T,
+    options: EncoderOptions,
+) -> Result<Vec<u8>, Error> {
+    let mut encoded = Vec::new();
+    to_writer_with_options(&mut encoded, value, options)?;
+    Ok(encoded)
+}
+
+/// Encode the given value into the given blocking [`io::Write`] sink, without an async runtime.
+pub fn to_writer<'en, W: io::Write, T: IntoStream<'en> + 'en>(
+    writer: W,
+    value: T,
+) -> Result<(), Error> {
+    to_writer_with_options(writer, value, EncoderOptions::default())
+}
+
+/// Encode the given value into the given blocking [`io::Write`] sink using the given `options`,
+/// without an async runtime.
+pub fn to_writer_with_options<'en, W: io::Write, T: IntoStream<'en> + 'en>(
+    mut writer: W,
+    value: T,
+    options: EncoderOptions,
+) -> Result<(), Error> {
+    let encoded = encode_with_options(value, options)?;
+
+    for chunk in block_on_stream(encoded) {
+        writer.write_all(&chunk?).map_err(Error::io)?;
+    }
+
+    Ok(())
+}
+
+/// Encode the given value into the given [`AsyncWrite`] sink.
+#[cfg(feature = "tokio-io")]
+pub async fn write_to<'en, W, T>(writer: W, value: T) -> Result<(), Error>
+where
+    W: AsyncWrite + Unpin,
+    T: IntoStream<'en> + 'en,
+{
+    write_to_with_options(writer, value, WriteOptions::default()).await
+}
+
+/// Encode the given value into the given [`AsyncWrite`] sink using the given `options`.
+#[cfg(feature = "tokio-io")]
+pub async fn write_to_with_options<'en, W, T>(
+    writer: W,
+    value: T,
+    options: WriteOptions,
+) -> Result<(), Error>
+where
+    W: AsyncWrite + Unpin,
+    T: IntoStream<'en> + 'en,
+{
+    let encoded = encode_with_options(value, options.encoder)?;
+    write::write_stream(write::TokioSink(writer), encoded, options).await
+}
+
+/// Encode the given stream of key-value pairs as a map into the given [`AsyncWrite`] sink.
+#[cfg(feature = "tokio-io")]
+pub async fn write_map_to<'en, W, K, V, S>(writer: W, seq: S) -> Result<(), Error>
+where
+    W: AsyncWrite + Unpin,
+    K: IntoStream<'en> + 'en,
+    V: IntoStream<'en> + 'en,
+    S: Stream<Item = (K, V)> + Send + Unpin + 'en,
+{
+    write_map_to_with_options(writer, seq, WriteOptions::default()).await
+}
+
+/// Encode the given stream of key-value pairs as a map into the given [`AsyncWrite`] sink
+/// using the given `options`.
+#[cfg(feature = "tokio-io")]
+pub async fn write_map_to_with_options<'en, W, K, V, S>(
+    writer: W,
+    seq: S,
+    options: WriteOptions,
+) -> Result<(), Error>
+where
+    W: AsyncWrite + Unpin,
+    K: IntoStream<'en> + 'en,
+    V: IntoStream<'en> + 'en,
+    S: Stream<Item = (K, V)> + Send + Unpin + 'en,
+{
+    let encoded = encode_map_with_options(seq, options.encoder);
+    write::write_stream(write::TokioSink(writer), encoded, options).await
+}
+
+/// Encode the given stream of elements as a list into the given [`AsyncWrite`] sink.
+#[cfg(feature = "tokio-io")]
+pub async fn write_seq_to<'en, W, T, S>(writer: W, seq: S) -> Result<(), Error>
+where
+    W: AsyncWrite + Unpin,
+    T: IntoStream<'en> + 'en,
+    S: Stream<Item = T> + Send + Unpin + 'en,
+{
+    write_seq_to_with_options(writer, seq, WriteOptions::default()).await
+}
+
+/// Encode the given stream of elements as a list into the given [`AsyncWrite`] sink
+/// using the given `options`.
+#[cfg(feature = "tokio-io")]
+pub async fn write_seq_to_with_options<'en, W, T, S>(
+    writer: W,
+    seq: S,
+    options: WriteOptions,
+) -> Result<(), Error>
+where
+    W: AsyncWrite + Unpin,
+    T: IntoStream<'en> + 'en,
+    S: Stream<Item = T> + Send + Unpin + 'en,
+{
+    let encoded = encode_seq_with_options(seq, options.encoder);
+    write::write_stream(write::TokioSink(writer), encoded, options).await
+}
+
+/// Encode the given value into the given [`futures::io::AsyncWrite`] sink.
+#[cfg(feature = "futures-io")]
+pub async fn write_to_futures<'en, W, T>(writer: W, value: T) -> Result<(), Error>
+where
+    W: futures::io::AsyncWrite + Unpin,
+    T: IntoStream<'en> + 'en,
+{
+    write_to_futures_with_options(writer, value, WriteOptions::default()).await
+}
+
+/// Encode the given value into the given [`futures::io::AsyncWrite`] sink
+/// using the given `options`.
+#[cfg(feature = "futures-io")]
+pub async fn write_to_futures_with_options<'en, W, T>(
+    writer: W,
+    value: T,
+    options: WriteOptions,
+) -> Result<(), Error>
+where
+    W: futures::io::AsyncWrite + Unpin,
+    T: IntoStream<'en> + 'en,
+{
+    let encoded = encode_with_options(value, options.encoder)?;
+    write::write_stream(write::FuturesSink(writer), encoded, options).await
+}
+
+/// Encode the given stream of key-value pairs as a map
+/// into the given [`futures::io::AsyncWrite`] sink.
+#[cfg(feature = "futures-io")]
+pub async fn write_map_to_futures<'en, W, K, V, S>(writer: W, seq: S) -> Result<(), Error>
+where
+    W: futures::io::AsyncWrite + Unpin,
+    K: IntoStream<'en> + 'en,
+    V: IntoStream<'en> + 'en,
+    S: Stream<Item = (K, V)> + Send + Unpin + 'en,
+{
+    write_map_to_futures_with_options(writer, seq, WriteOptions::default()).await
+}
+
+/// Encode the given stream of key-value pairs as a map
+/// into the given [`futures::io::AsyncWrite`] sink using the given `options`.
+#[cfg(feature = "futures-io")]
+pub async fn write_map_to_futures_with_options<'en, W, K, V, S>(
+    writer: W,
+    seq: S,
+    options: WriteOptions,
+) -> Result<(), Error>
+where
+    W: futures::io::AsyncWrite + Unpin,
+    K: IntoStream<'en> + 'en,
+    V: IntoStream<'en> + 'en,
+    S: Stream<Item = (K, V)> + Send + Unpin + 'en,
+{
+    let encoded = encode_map_with_options(seq, options.encoder);
+    write::write_stream(write::FuturesSink(writer), encoded, options).await
+}
+
+/// Encode the given stream of elements as a list into the given [`futures::io::AsyncWrite`] sink.
+#[cfg(feature = "futures-io")]
+pub async fn write_seq_to_futures<'en, W, T, S>(writer: W, seq: S) -> Result<(), Error>
+where
+    W: futures::io::AsyncWrite + Unpin,
+    T: IntoStream<'en> + 'en,
+    S: Stream<Item = T> + Send + Unpin + 'en,
+{
+    write_seq_to_futures_with_options(writer, seq, WriteOptions::default()).await
+}
+
+/// Encode the given stream of elements as a list into the given [`futures::io::AsyncWrite`] sink
+/// using the given `options`.
+#[cfg(feature = "futures-io")]
+pub async fn write_seq_to_futures_with_options<'en, W, T, S>(
+    writer: W,
+    seq: S,
+    options: WriteOptions,
+) -> Result<(), Error>
+where
+    W: futures::io::AsyncWrite + Unpin,
+    T: IntoStream<'en> + 'en,
+    S: Stream<Item = T> + Send + Unpin + 'en,
+{
+    let encoded = encode_seq_with_options(seq, options.encoder);
+    write::write_stream(write::FuturesSink(writer), encoded, options).await
 }
 
 /// Given a stream of encodable key-value pairs, return an encoded map stream.
@@ -515,7 +1048,27 @@ where
     V: IntoStream<'en> + 'en,
     S: Stream<Item = (K, V)> + Send + Unpin + 'en,
 {
-    stream::encode_map(seq)
+    encode_map_with_options(seq, EncoderOptions::default())
+}
+
+/// Given a stream of encodable key-value pairs, return a map stream encoded using the given
+/// `options`.
+///
+/// If [`EncoderOptions::canonical_maps`] is set, nothing is encoded until `seq` ends,
+/// since its entries must be sorted first.
+pub fn encode_map_with_options<'en, K, V, S>(
+    seq: S,
+    options: EncoderOptions,
+) -> impl Stream<Item = Result<Bytes, Error>> + Send + Unpin + 'en
+where
+    K: IntoStream<'en> + 'en,
+    V: IntoStream<'en> + 'en,
+    S: Stream<Item = (K, V)> + Send + Unpin + 'en,
+{
+    match en::Encoder::encode_map_stream(Encoder::with_options(options), seq) {
+        Ok(encoded) => finish(encoded, options),
+        Err(cause) => Box::pin(futures::stream::once(future::ready(Err(cause)))),
+    }
 }
 
 /// Given a stream of encodable elements, return an encoded sequence stream.
@@ -526,10 +1079,41 @@ where
     T: IntoStream<'en> + 'en,
     S: Stream<Item = T> + Send + Unpin + 'en,
 {
-    stream::encode_list(seq)
+    encode_seq_with_options(seq, EncoderOptions::default())
+}
+
+/// Given a stream of encodable elements, return a sequence stream encoded using the given
+/// `options`.
+pub fn encode_seq_with_options<'en, T, S>(
+    seq: S,
+    options: EncoderOptions,
+) -> impl Stream<Item = Result<Bytes, Error>> + Send + Unpin + 'en
+where
+    T: IntoStream<'en> + 'en,
+    S: Stream<Item = T> + Send + Unpin + 'en,
+{
+    match en::Encoder::encode_seq_stream(Encoder::with_options(options), seq) {
+        Ok(encoded) => finish(encoded, options),
+        Err(cause) => Box::pin(futures::stream::once(future::ready(Err(cause)))),
+    }
 }
 
-fn encode_array<'en, const SIZE: usize, E, T, S>(dtype: Type, chunks: S) -> ByteStream<'en>
+/// Apply the `options` which only apply to a complete stream to the `encoded` value,
+/// i.e. its magic header and chunk size.
+fn finish<'en>(encoded: ByteStream<'en>, options: EncoderOptions) -> ByteStream<'en> {
+    let encoded = match options.magic_header {
+        Some(header) => Box::pin(delimiter(header).chain(encoded)),
+        None => encoded,
+    };
+
+    if options.chunk_size > 0 {
+        Box::pin(stream::Coalesce::new(encoded, options.chunk_size))
+    } else {
+        encoded
+    }
+}
+
+fn encode_array_escaped<'en, const SIZE: usize, E, T, S>(dtype: Type, chunks: S) -> ByteStream<'en>
 where
     E: IntoBytes<SIZE>,
     T: IntoIterator<Item = E>,
@@ -543,22 +1127,82 @@ where
     let end = delimiter(ARRAY_DELIMIT);
 
     let contents = chunks.map(|chunk| {
-        let mut encoded = BytesMut::new();
+        let chunk = chunk.into_iter();
+        let mut bytes = Vec::with_capacity(chunk.size_hint().0 * SIZE);
+        for b in chunk {
+            bytes.extend_from_slice(&b.into_bytes());
+        }
+
+        let mut encoded = BytesMut::with_capacity(bytes.len() + 1);
+        escape(&bytes, ARRAY_DELIMIT, &mut encoded);
+        Ok(encoded.into())
+    });
+
+    let encoded: ByteStream = Box::pin(start.chain(contents).chain(end));
+    encoded
+}
+
+fn encode_array_prefixed<'en, const SIZE: usize, E, T, S>(dtype: Type, chunks: S) -> ByteStream<'en>
+where
+    E: IntoBytes<SIZE>,
+    T: IntoIterator<Item = E>,
+    S: Stream<Item = T> + Send + Unpin + 'en,
+{
+    let mut start = BytesMut::with_capacity(2);
+    start.extend_from_slice(ARRAY_PREFIXED);
+    start.put_u8(dtype.to_u8().expect("type bit"));
 
-        for b in chunk.into_iter() {
-            for byte in b.into_bytes() {
-                let as_slice = std::slice::from_ref(&byte);
-                if as_slice == ARRAY_DELIMIT || as_slice == ESCAPE {
-                    encoded.extend_from_slice(ESCAPE);
-                }
+    let start = futures::stream::once(future::ready(Ok(Bytes::from(start))));
+    let end = delimiter(&[0]);
 
-                encoded.put_u8(byte);
-            }
+    let contents = chunks.filter_map(|chunk| {
+        let chunk = chunk.into_iter();
+        let mut bytes = Vec::with_capacity(chunk.size_hint().0 * SIZE);
+        for b in chunk {
+            bytes.extend_from_slice(&b.into_bytes());
         }
 
-        Ok(encoded.into())
+        // an empty chunk would mark the end of the array
+        let encoded = if bytes.is_empty() {
+            None
+        } else {
+            let mut encoded = BytesMut::with_capacity(bytes.len() + varint::MAX_LEN);
+            put_chunk(&bytes, SIZE, &mut encoded);
+            Some(Ok(encoded.into()))
+        };
+
+        future::ready(encoded)
     });
 
     let encoded: ByteStream = Box::pin(start.chain(contents).chain(end));
     encoded
 }
+
+/// Append the length, in elements of `size` bytes, of the given chunk of `elements` to `output`,
+/// followed by the elements themselves.
+fn put_chunk(elements: &[u8], size: usize, output: &mut BytesMut) {
+    if !elements.is_empty() {
+        varint::put((elements.len() / size) as u128, output);
+        output.extend_from_slice(elements);
+    }
+}
+
+/// Append `value` to `output`, prefixing each byte in `control`, and each [`ESCAPE`] byte,
+/// with [`ESCAPE`].
+pub(crate) fn escape(value: &[u8], control: &[u8], output: &mut BytesMut) {
+    let mut start = 0;
+    let mut escape_at = |i: usize| {
+        output.extend_from_slice(&value[start..i]);
+        output.extend_from_slice(ESCAPE);
+        start = i;
+    };
+
+    match *control {
+        [c] => memchr2_iter(c, ESCAPE[0], value).for_each(&mut escape_at),
+        [a, b] if a == b => memchr2_iter(a, ESCAPE[0], value).for_each(&mut escape_at),
+        [a, b] => memchr3_iter(a, b, ESCAPE[0], value).for_each(&mut escape_at),
+        _ => unreachable!("more than two control characters to escape"),
+    }
+
+    output.extend_from_slice(&value[start..]);
+}
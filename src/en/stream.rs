@@ -1,11 +1,12 @@
+use std::collections::VecDeque;
 use std::pin::Pin;
 use std::task::{self, Poll};
 
-use bytes::Bytes;
+use bytes::{Bytes, BytesMut};
 use destream::en::{self, IntoStream};
-use futures::ready;
-use futures::stream::{Fuse, FusedStream, Stream, StreamExt};
+use futures::stream::{self, Fuse, FusedStream, Stream, StreamExt, TryStreamExt};
 use futures::task::Context;
+use futures::{future, ready};
 use pin_project::pin_project;
 
 use crate::constants::*;
@@ -22,9 +23,13 @@ struct MapEntryStream<'en> {
 }
 
 impl<'en> MapEntryStream<'en> {
-    fn new<K: IntoStream<'en>, V: IntoStream<'en>>(key: K, value: V) -> Result<Self, super::Error> {
-        let key = key.into_stream(Encoder)?;
-        let value = value.into_stream(Encoder)?;
+    fn new<K: IntoStream<'en>, V: IntoStream<'en>>(
+        encoder: Encoder,
+        key: K,
+        value: V,
+    ) -> Result<Self, super::Error> {
+        let key = key.into_stream(encoder)?;
+        let value = value.into_stream(encoder)?;
 
         Ok(Self {
             key: key.fuse(),
@@ -61,6 +66,48 @@ impl<'en> FusedStream for MapEntryStream<'en> {
     }
 }
 
+/// A stream of the encoded items of a list or map which has already been collected,
+/// which polls each item in turn rather than nesting them, so that neither polling nor dropping
+/// it costs more stack space as the number of items grows.
+pub struct DelimitedStream<'en> {
+    start: Option<&'static [u8]>,
+    items: VecDeque<ByteStream<'en>>,
+    end: Option<&'static [u8]>,
+}
+
+impl<'en> DelimitedStream<'en> {
+    pub fn new(start: &'static [u8], items: VecDeque<ByteStream<'en>>, end: &'static [u8]) -> Self {
+        Self {
+            start: Some(start),
+            items,
+            end: Some(end),
+        }
+    }
+}
+
+impl<'en> Stream for DelimitedStream<'en> {
+    type Item = Result<Bytes, super::Error>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cxt: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = &mut *self;
+
+        if let Some(start) = this.start.take() {
+            return Poll::Ready(Some(Ok(Bytes::from_static(start))));
+        }
+
+        while let Some(item) = this.items.front_mut() {
+            match ready!(item.as_mut().poll_next(cxt)) {
+                Some(result) => return Poll::Ready(Some(result)),
+                None => {
+                    this.items.pop_front();
+                }
+            }
+        }
+
+        Poll::Ready(this.end.take().map(|end| Ok(Bytes::from_static(end))))
+    }
+}
+
 #[pin_project]
 struct TBONEncodingStream<
     I: Stream<Item = Result<Bytes, super::Error>>,
@@ -126,9 +173,10 @@ impl<I: Stream<Item = Result<Bytes, super::Error>>, S: Stream<Item = Result<I, s
 }
 
 pub fn encode_list<'en, I: IntoStream<'en>, S: Stream<Item = I> + Send + Unpin + 'en>(
+    encoder: Encoder,
     seq: S,
 ) -> impl Stream<Item = Result<Bytes, super::Error>> + 'en {
-    let source = seq.map(|item| item.into_stream(Encoder));
+    let source = seq.map(move |item| item.into_stream(encoder));
 
     TBONEncodingStream {
         source: source.fuse(),
@@ -146,9 +194,10 @@ pub fn encode_map<
     V: IntoStream<'en>,
     S: Stream<Item = (K, V)> + Send + Unpin + 'en,
 >(
+    encoder: Encoder,
     seq: S,
 ) -> impl Stream<Item = Result<Bytes, super::Error>> + Send + Unpin + 'en {
-    let source = seq.map(|(key, value)| MapEntryStream::new(key, value));
+    let source = seq.map(move |(key, value)| MapEntryStream::new(encoder, key, value));
 
     TBONEncodingStream {
         source: source.fuse(),
@@ -159,3 +208,103 @@ pub fn encode_map<
         end: MAP_END,
     }
 }
+
+/// Encode a map whose entries are sorted by their encoded keys, rejecting any duplicate key.
+///
+/// Every entry is collected and each key is buffered in order to sort the entries, so nothing is
+/// emitted until the source ends. The values themselves are streamed as usual.
+pub fn encode_canonical_map<'en, S>(entries: S) -> ByteStream<'en>
+where
+    S: Stream<Item = Result<(ByteStream<'en>, ByteStream<'en>), super::Error>> + Send + Unpin + 'en,
+{
+    let encoded = async move {
+        let entries: Vec<(ByteStream<'en>, ByteStream<'en>)> = entries.try_collect().await?;
+
+        let mut sorted = Vec::with_capacity(entries.len());
+        for (key, value) in entries {
+            let key: Vec<Bytes> = key.try_collect().await?;
+            sorted.push((key.concat(), value));
+        }
+
+        sorted.sort_by(|(l, _), (r, _)| l.cmp(r));
+
+        if sorted.windows(2).any(|pair| pair[0].0 == pair[1].0) {
+            let cause: super::Error = en::Error::custom("duplicate key in a canonical map");
+            return Err(cause);
+        }
+
+        let entries = sorted.into_iter().map(|(key, value)| {
+            let key = stream::once(future::ready(Ok(Bytes::from(key))));
+            key.chain(value)
+        });
+
+        let start = stream::once(future::ready(Ok(Bytes::from_static(MAP_BEGIN))));
+        let end = stream::once(future::ready(Ok(Bytes::from_static(MAP_END))));
+        Ok::<_, super::Error>(start.chain(stream::iter(entries).flatten()).chain(end))
+    };
+
+    Box::pin(Box::pin(stream::once(encoded)).try_flatten())
+}
+
+/// A stream which merges adjacent chunks of its source into chunks of about `size` bytes.
+///
+/// Whatever has been buffered is emitted as soon as the source is pending, so that a streaming
+/// value is never delayed waiting for a full chunk.
+#[pin_project]
+pub struct Coalesce<S> {
+    #[pin]
+    source: Fuse<S>,
+
+    buffer: BytesMut,
+    next: Option<Result<Bytes, super::Error>>,
+    size: usize,
+}
+
+impl<S: Stream<Item = Result<Bytes, super::Error>>> Coalesce<S> {
+    pub fn new(source: S, size: usize) -> Self {
+        Self {
+            source: source.fuse(),
+            buffer: BytesMut::new(),
+            next: None,
+            size,
+        }
+    }
+}
+
+impl<S: Stream<Item = Result<Bytes, super::Error>>> Stream for Coalesce<S> {
+    type Item = Result<Bytes, super::Error>;
+
+    fn poll_next(self: Pin<&mut Self>, cxt: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let mut this = self.project();
+
+        if let Some(next) = this.next.take() {
+            return Poll::Ready(Some(next));
+        }
+
+        loop {
+            let next = match this.source.as_mut().poll_next(cxt) {
+                Poll::Ready(Some(Ok(chunk))) if chunk.len() < *this.size => {
+                    this.buffer.extend_from_slice(&chunk);
+
+                    if this.buffer.len() < *this.size {
+                        continue;
+                    } else {
+                        None
+                    }
+                }
+                // a large chunk is passed through rather than copied
+                Poll::Ready(Some(result)) => Some(result),
+                Poll::Ready(None) if this.buffer.is_empty() => return Poll::Ready(None),
+                Poll::Pending if this.buffer.is_empty() => return Poll::Pending,
+                Poll::Ready(None) | Poll::Pending => None,
+            };
+
+            return if this.buffer.is_empty() {
+                Poll::Ready(next)
+            } else {
+                *this.next = next;
+                Poll::Ready(Some(Ok(this.buffer.split().freeze())))
+            };
+        }
+    }
+}
@@ -0,0 +1,126 @@
+use std::io;
+
+use bytes::{Bytes, BytesMut};
+use futures::stream::{Stream, StreamExt};
+
+use super::{EncoderOptions, Error};
+
+/// Options which control how an encoded stream is written to an asynchronous sink.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct WriteOptions {
+    buffer_size: usize,
+    flush_each_write: bool,
+    pub(super) encoder: EncoderOptions,
+}
+
+impl WriteOptions {
+    /// Construct a new set of [`WriteOptions`], which encode with the default
+    /// [`EncoderOptions`], write each encoded chunk as soon as it's available, and flush the sink
+    /// once the stream is finished.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Coalesce encoded chunks into writes of at least `buffer_size` bytes.
+    pub fn buffer_size(mut self, buffer_size: usize) -> Self {
+        self.buffer_size = buffer_size;
+        self
+    }
+
+    /// Flush the sink after every write, rather than only at the end of the stream.
+    pub fn flush_each_write(mut self, flush_each_write: bool) -> Self {
+        self.flush_each_write = flush_each_write;
+        self
+    }
+
+    /// Encode the stream to write using the given [`EncoderOptions`].
+    pub fn encoder_options(mut self, encoder: EncoderOptions) -> Self {
+        self.encoder = encoder;
+        self
+    }
+}
+
+/// Methods common to any asynchronous sink of encoded bytes
+pub trait Sink {
+    async fn write_all(&mut self, chunk: &[u8]) -> io::Result<()>;
+
+    async fn flush(&mut self) -> io::Result<()>;
+}
+
+/// A [`tokio::io::AsyncWrite`] sink
+#[cfg(feature = "tokio-io")]
+pub struct TokioSink<W>(pub W);
+
+#[cfg(feature = "tokio-io")]
+impl<W: tokio::io::AsyncWrite + Unpin> Sink for TokioSink<W> {
+    async fn write_all(&mut self, chunk: &[u8]) -> io::Result<()> {
+        tokio::io::AsyncWriteExt::write_all(&mut self.0, chunk).await
+    }
+
+    async fn flush(&mut self) -> io::Result<()> {
+        tokio::io::AsyncWriteExt::flush(&mut self.0).await
+    }
+}
+
+/// A [`futures::io::AsyncWrite`] sink
+#[cfg(feature = "futures-io")]
+pub struct FuturesSink<W>(pub W);
+
+#[cfg(feature = "futures-io")]
+impl<W: futures::io::AsyncWrite + Unpin> Sink for FuturesSink<W> {
+    async fn write_all(&mut self, chunk: &[u8]) -> io::Result<()> {
+        futures::io::AsyncWriteExt::write_all(&mut self.0, chunk).await
+    }
+
+    async fn flush(&mut self) -> io::Result<()> {
+        futures::io::AsyncWriteExt::flush(&mut self.0).await
+    }
+}
+
+/// Write the given `encoded` stream into the given `sink`.
+pub async fn write_stream<W, S>(
+    mut sink: W,
+    mut encoded: S,
+    options: WriteOptions,
+) -> Result<(), Error>
+where
+    W: Sink,
+    S: Stream<Item = Result<Bytes, Error>> + Unpin,
+{
+    async fn write<W: Sink>(
+        sink: &mut W,
+        chunk: &[u8],
+        options: &WriteOptions,
+    ) -> Result<(), Error> {
+        sink.write_all(chunk).await.map_err(Error::io)?;
+
+        if options.flush_each_write {
+            sink.flush().await.map_err(Error::io)?;
+        }
+
+        Ok(())
+    }
+
+    let mut buffer = BytesMut::new();
+
+    while let Some(chunk) = encoded.next().await {
+        let chunk = chunk?;
+
+        if buffer.is_empty() && chunk.len() >= options.buffer_size {
+            write(&mut sink, &chunk, &options).await?;
+        } else {
+            buffer.extend_from_slice(&chunk);
+
+            if buffer.len() >= options.buffer_size {
+                write(&mut sink, &buffer, &options).await?;
+                buffer.clear();
+            }
+        }
+    }
+
+    if !buffer.is_empty() {
+        write(&mut sink, &buffer, &options).await?;
+    }
+
+    sink.flush().await.map_err(Error::io)
+}
@@ -0,0 +1,53 @@
+//! Typed hooks which let the types in this crate call the methods of the TBON
+//! [`crate::en::Encoder`] and [`crate::de::Decoder`] which [`destream`] has no method for, like
+//! [`crate::en::Encoder::encode_i128`], when they're only known as a generic
+//! [`destream::en::Encoder`] or [`destream::de::Visitor`].
+
+use std::any::TypeId;
+use std::marker::PhantomData;
+use std::mem::{self, ManuallyDrop};
+use std::ptr;
+
+/// Return the [`TypeId`] of `T`, ignoring its lifetimes.
+fn type_id<T: ?Sized>() -> TypeId {
+    trait NonStaticAny {
+        fn type_id(&self) -> TypeId
+        where
+            Self: 'static;
+    }
+
+    impl<T: ?Sized> NonStaticAny for PhantomData<T> {
+        fn type_id(&self) -> TypeId
+        where
+            Self: 'static,
+        {
+            TypeId::of::<T>()
+        }
+    }
+
+    let phantom = PhantomData::<T>;
+
+    // SAFETY: the lifetime of the trait object is only extended to call `type_id`,
+    // which doesn't depend on it
+    let phantom =
+        unsafe { mem::transmute::<&dyn NonStaticAny, &(dyn NonStaticAny + 'static)>(&phantom) };
+
+    phantom.type_id()
+}
+
+/// Return `true` if `U` is the type `T`, which must not have any lifetime parameters.
+pub(crate) fn is<T: 'static, U>() -> bool {
+    type_id::<U>() == TypeId::of::<T>()
+}
+
+/// Move the given `value` of type `T` into the type `U`.
+///
+/// # Safety
+/// `T` and `U` must be the same type, including their lifetimes, e.g. because [`is`] is `true`
+/// and the type passed to [`is`] has no lifetime parameters.
+pub(crate) unsafe fn cast<T, U>(value: T) -> U {
+    debug_assert_eq!(type_id::<T>(), type_id::<U>());
+
+    let value = ManuallyDrop::new(value);
+    ptr::read(&*value as *const T as *const U)
+}
@@ -0,0 +1,1195 @@
+//! Transcode a TBON stream to JSON, and back, without buffering the whole document.
+//!
+//! Example:
+//! ```
+//! # use futures::executor::block_on;
+//! # use futures::{stream, TryStreamExt};
+//! # use bytes::Bytes;
+//! let tbon = Bytes::from(tbon::to_vec(("one", 2u16, vec![3.5f32])).unwrap());
+//! let source = stream::iter([Ok::<Bytes, std::io::Error>(tbon.clone())]);
+//! let json: Vec<Bytes> = block_on(tbon::json::to_json(source).try_collect()).unwrap();
+//! assert_eq!(json.concat(), br#"["one",{"/u16":2},[{"/f32":3.5}]]"#);
+//!
+//! let source = stream::iter(json.into_iter().map(Ok::<Bytes, std::io::Error>));
+//! let encoded: Vec<Bytes> = block_on(tbon::json::from_json(source).try_collect()).unwrap();
+//! assert_eq!(encoded.concat(), tbon);
+//! ```
+//!
+//! Every TBON value is represented in JSON using these conventions, so that typed arrays and
+//! numbers of each width survive a round trip:
+//!  - None, booleans, strings, and lists are `null`, booleans, strings, and JSON arrays
+//!  - a 64-bit int is a JSON integer, and a finite 64-bit float is a JSON number with a decimal
+//!    point or an exponent, e.g. `1.0`
+//!  - any other number is an object with a single type tag, like `{"/u16": 7}`, where the tag
+//!    is one of `/bool`, `/f32`, `/f64`, `/i8`, `/i16`, `/i32`, `/i64`, `/i128`, `/u8`, `/u16`,
+//!    `/u32`, `/u64`, or `/u128` (or, with the `half` feature, `/f16` or `/bf16`), and a float
+//!    which is not finite is one of the strings "NaN", "inf" or "-inf"
+//!  - a typed array is an object with a single type tag, like `{"/u8": [1, 2, 3]}`
+//!  - a `u64`, `i128` or `u128` which is too large to be an `i64` is a string, like
+//!    `{"/u64": "18446744073709551615"}`
+//!  - a UUID is a string with the tag `/uuid`, like
+//!    `{"/uuid": "67e55044-10b1-426f-9247-bb680e5fe0c8"}`
+//!  - a map whose keys are all strings is an object; a key which begins with "/" is escaped
+//!    by prefixing it with another "/", e.g. `{"//path": 1}` for the key "/path"
+//!  - any other map is an object with the tag `/map` whose value is a list of `[key, value]`
+//!    entries, like `{"/map": [[1, "one"]]}`
+//!
+//! A map whose keys include both strings and other values cannot be transcoded to JSON.
+
+use std::fmt::{self, Write};
+use std::future::Future;
+use std::pin::Pin;
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll};
+
+use bytes::{BufMut, Bytes, BytesMut};
+use destream::de::{self, ArrayAccess, MapAccess, SeqAccess, Visitor};
+use destream::{FromStream, IgnoredAny};
+use futures::channel::mpsc;
+use futures::stream::{Stream, StreamExt, TryStreamExt};
+use futures::SinkExt;
+use num_traits::{Float, ToPrimitive};
+use uuid::Uuid;
+
+use super::constants::*;
+use super::de::{try_decode, Error as DecodeError, Native, VisitNative};
+use super::en::{escape, Error as EncodeError, CHUNK_SIZE};
+
+const CHANNEL_SIZE: usize = 8;
+const MAP_TAG: &str = "/map";
+
+/// Transcode the given TBON-encoded `source` stream into a stream of JSON.
+pub fn to_json<E, S>(source: S) -> impl Stream<Item = Result<Bytes, DecodeError>> + Send + Unpin
+where
+    E: fmt::Display,
+    S: Stream<Item = Result<Bytes, E>> + Send + Unpin + 'static,
+{
+    Transcode::new(|output: Output<DecodeError>| async move {
+        try_decode::<_, _, ToJson>(output.clone(), source).await?;
+        Ok(output.take())
+    })
+}
+
+/// Transcode the given JSON-encoded `source` stream into a stream of TBON.
+pub fn from_json<E, S>(source: S) -> impl Stream<Item = Result<Bytes, EncodeError>> + Send + Unpin
+where
+    E: fmt::Display,
+    S: Stream<Item = Result<Bytes, E>> + Send + Unpin + 'static,
+{
+    let mut escapes = Escapes::default();
+    let source = source.map_ok(move |chunk| escapes.normalize(&chunk));
+
+    Transcode::new(|output: Output<EncodeError>| async move {
+        destream_json::try_decode::<_, _, FromJson>(output.clone(), source)
+            .await
+            .map_err(|cause| en_error(format!("invalid JSON: {}", cause)))?;
+
+        Ok(output.take())
+    })
+}
+
+fn en_error<I: fmt::Display>(info: I) -> EncodeError {
+    destream::en::Error::custom(info)
+}
+
+/// The state of a JSON string escape sequence which may be split across chunks
+#[derive(Default)]
+enum Escape {
+    #[default]
+    None,
+    Backslash,
+    Unicode(u32, usize),
+}
+
+/// Rewrites the escape sequences in JSON strings, other than `\"` and `\\`, as the characters
+/// they represent, since [`destream_json`] only recognizes those two.
+#[derive(Default)]
+struct Escapes {
+    in_string: bool,
+    escape: Escape,
+    high_surrogate: Option<u32>,
+}
+
+impl Escapes {
+    fn normalize(&mut self, chunk: &[u8]) -> Bytes {
+        if !self.in_string && memchr::memchr2(b'"', b'\\', chunk).is_none() {
+            return Bytes::copy_from_slice(chunk);
+        }
+
+        let mut normalized = BytesMut::with_capacity(chunk.len());
+
+        for byte in chunk.iter().copied() {
+            match self.escape {
+                Escape::None if !self.in_string => {
+                    self.in_string = byte == b'"';
+                    normalized.put_u8(byte);
+                }
+                Escape::None => match byte {
+                    b'\\' => self.escape = Escape::Backslash,
+                    byte => {
+                        self.surrogate(&mut normalized);
+                        self.in_string = byte != b'"';
+                        normalized.put_u8(byte);
+                    }
+                },
+                Escape::Backslash => {
+                    self.escape = Escape::None;
+
+                    let c = match byte {
+                        b'u' => {
+                            self.escape = Escape::Unicode(0, 0);
+                            continue;
+                        }
+                        b'b' => '\u{8}',
+                        b'f' => '\u{c}',
+                        b'n' => '\n',
+                        b'r' => '\r',
+                        b't' => '\t',
+                        other => {
+                            // leave any other escape sequence, valid or not, to the decoder
+                            self.surrogate(&mut normalized);
+                            normalized.extend_from_slice(&[b'\\', other]);
+                            continue;
+                        }
+                    };
+
+                    self.char(c as u32, &mut normalized);
+                }
+                Escape::Unicode(code, len) => {
+                    let digit = match (byte as char).to_digit(16) {
+                        Some(digit) => digit,
+                        None => {
+                            // not a valid escape sequence, so let the decoder reject it
+                            self.escape = Escape::None;
+                            normalized.extend_from_slice(b"\\u");
+                            normalized.put_u8(byte);
+                            continue;
+                        }
+                    };
+
+                    let code = (code << 4) | digit;
+                    if len == 3 {
+                        self.escape = Escape::None;
+                        self.char(code, &mut normalized);
+                    } else {
+                        self.escape = Escape::Unicode(code, len + 1);
+                    }
+                }
+            }
+        }
+
+        normalized.freeze()
+    }
+
+    fn char(&mut self, code: u32, normalized: &mut BytesMut) {
+        let code = match (self.high_surrogate.take(), code) {
+            (None, 0xD800..=0xDBFF) => {
+                self.high_surrogate = Some(code);
+                return;
+            }
+            (Some(high), 0xDC00..=0xDFFF) => 0x10000 + ((high - 0xD800) << 10) + (code - 0xDC00),
+            (Some(_), code) => {
+                normalized.extend_from_slice(char::REPLACEMENT_CHARACTER.to_string().as_bytes());
+                code
+            }
+            (None, code) => code,
+        };
+
+        match char::from_u32(code).unwrap_or(char::REPLACEMENT_CHARACTER) {
+            '"' => normalized.extend_from_slice(b"\\\""),
+            '\\' => normalized.extend_from_slice(b"\\\\"),
+            c => normalized.extend_from_slice(c.encode_utf8(&mut [0; 4]).as_bytes()),
+        }
+    }
+
+    /// Write a replacement character for a high surrogate which is not followed by a low one.
+    fn surrogate(&mut self, normalized: &mut BytesMut) {
+        if self.high_surrogate.take().is_some() {
+            normalized.extend_from_slice(char::REPLACEMENT_CHARACTER.to_string().as_bytes());
+        }
+    }
+}
+
+/// The destination of transcoded bytes, which are written to a shared buffer and sent to the
+/// output stream in chunks of at least [`CHUNK_SIZE`], the default chunk size of an
+/// [`super::en::EncoderOptions`].
+///
+/// An [`Output`] without a sender is only a buffer, e.g. to read a map key in full before
+/// deciding how to transcode it.
+pub(crate) struct Output<E> {
+    pending: Arc<Mutex<BytesMut>>,
+    tx: Option<mpsc::Sender<Result<Bytes, E>>>,
+}
+
+impl<E> Clone for Output<E> {
+    fn clone(&self) -> Self {
+        Self {
+            pending: self.pending.clone(),
+            tx: self.tx.clone(),
+        }
+    }
+}
+
+impl<E: Send> Output<E> {
+    fn buffer() -> Self {
+        Self {
+            pending: Arc::new(Mutex::new(BytesMut::new())),
+            tx: None,
+        }
+    }
+
+    fn write<F: FnOnce(&mut BytesMut)>(&self, write: F) {
+        let mut pending = self.pending.lock().expect("pending output");
+        write(&mut pending)
+    }
+
+    fn write_slice(&self, bytes: &[u8]) {
+        self.write(|pending| pending.extend_from_slice(bytes))
+    }
+
+    /// Take all the bytes written to this [`Output`] which have not been sent yet.
+    fn take(&self) -> Bytes {
+        let mut pending = self.pending.lock().expect("pending output");
+        pending.split().freeze()
+    }
+
+    /// Send the pending bytes to the output stream, if there are enough of them.
+    async fn flush<Err: de::Error>(&self) -> Result<(), Err> {
+        let tx = match &self.tx {
+            Some(tx) => tx,
+            None => return Ok(()),
+        };
+
+        let chunk = {
+            let mut pending = self.pending.lock().expect("pending output");
+            if pending.len() >= CHUNK_SIZE {
+                pending.split().freeze()
+            } else {
+                return Ok(());
+            }
+        };
+
+        tx.clone()
+            .send(Ok(chunk))
+            .await
+            .map_err(|_| de::Error::custom("the output stream was dropped"))
+    }
+}
+
+type Driver<E> = Pin<Box<dyn Future<Output = Result<Bytes, E>> + Send>>;
+
+/// A stream of transcoded bytes, which drives the transcoding future as it's polled.
+///
+/// The bounded channel between the two limits how much output can be pending at once.
+struct Transcode<E> {
+    driver: Option<Driver<E>>,
+    rx: mpsc::Receiver<Result<Bytes, E>>,
+    last: Option<Result<Bytes, E>>,
+}
+
+impl<E: Send + 'static> Transcode<E> {
+    fn new<F, Fut>(transcode: F) -> Self
+    where
+        F: FnOnce(Output<E>) -> Fut,
+        Fut: Future<Output = Result<Bytes, E>> + Send + 'static,
+    {
+        let (tx, rx) = mpsc::channel(CHANNEL_SIZE);
+        let output = Output {
+            pending: Arc::new(Mutex::new(BytesMut::new())),
+            tx: Some(tx),
+        };
+
+        Self {
+            driver: Some(Box::pin(transcode(output))),
+            rx,
+            last: None,
+        }
+    }
+}
+
+// the driver is already pinned on the heap, and nothing else needs to be pinned
+impl<E> Unpin for Transcode<E> {}
+
+impl<E> Stream for Transcode<E> {
+    type Item = Result<Bytes, E>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        if let Some(driver) = &mut self.driver {
+            if let Poll::Ready(result) = driver.as_mut().poll(cx) {
+                self.driver = None;
+                self.last = match result {
+                    Ok(chunk) if chunk.is_empty() => None,
+                    result => Some(result),
+                };
+            }
+        }
+
+        match self.rx.poll_next_unpin(cx) {
+            Poll::Ready(Some(chunk)) => Poll::Ready(Some(chunk)),
+            // the channel only closes once the driver has finished and dropped every sender
+            Poll::Ready(None) => Poll::Ready(self.last.take()),
+            Poll::Pending => Poll::Pending,
+        }
+    }
+}
+
+/// A TBON value which has been transcoded to JSON
+pub(crate) struct ToJson;
+
+impl FromStream for ToJson {
+    type Context = Output<DecodeError>;
+
+    async fn from_stream<D: de::Decoder>(
+        output: Output<DecodeError>,
+        decoder: &mut D,
+    ) -> Result<Self, D::Error> {
+        decoder.decode_any(ToJsonVisitor { output }).await
+    }
+}
+
+pub(crate) struct ToJsonVisitor {
+    output: Output<DecodeError>,
+}
+
+impl ToJsonVisitor {
+    fn tagged<N: fmt::Display>(self, dtype: &str, n: N) -> ToJson {
+        self.output
+            .write(|pending| write!(pending, "{{\"/{}\":{}}}", dtype, n).expect("JSON"));
+
+        ToJson
+    }
+
+    fn tagged_float<F: Float + fmt::Debug>(self, dtype: &str, f: F) -> ToJson {
+        self.output.write(|pending| {
+            write!(pending, "{{\"/{}\":", dtype).expect("JSON");
+            write_float(f, pending);
+            pending.put_u8(b'}');
+        });
+
+        ToJson
+    }
+
+    async fn array<T, A>(
+        self,
+        dtype: &str,
+        access: A,
+        write: fn(T, &mut BytesMut),
+    ) -> Result<ToJson, A::Error>
+    where
+        T: Copy + Default + Send,
+        A: ArrayAccess<T>,
+    {
+        let mut access = access;
+        let mut buffer = [T::default(); 1024];
+        let mut first = true;
+
+        self.output
+            .write(|pending| write!(pending, "{{\"/{}\":[", dtype).expect("JSON"));
+
+        loop {
+            let len = access.buffer(&mut buffer).await?;
+            if len == 0 {
+                break;
+            }
+
+            self.output.write(|pending| {
+                for element in &buffer[..len] {
+                    if first {
+                        first = false;
+                    } else {
+                        pending.put_u8(b',');
+                    }
+
+                    write(*element, pending);
+                }
+            });
+
+            self.output.flush().await?;
+        }
+
+        self.output.write_slice(b"]}");
+        Ok(ToJson)
+    }
+}
+
+impl Visitor for ToJsonVisitor {
+    type Value = ToJson;
+
+    fn expecting() -> &'static str {
+        "a TBON value"
+    }
+
+    fn visit_bool<E: de::Error>(self, v: bool) -> Result<ToJson, E> {
+        self.output
+            .write_slice(if v { b"true".as_slice() } else { b"false" });
+
+        Ok(ToJson)
+    }
+
+    fn visit_i8<E: de::Error>(self, v: i8) -> Result<ToJson, E> {
+        Ok(self.tagged("i8", v))
+    }
+
+    fn visit_i16<E: de::Error>(self, v: i16) -> Result<ToJson, E> {
+        Ok(self.tagged("i16", v))
+    }
+
+    fn visit_i32<E: de::Error>(self, v: i32) -> Result<ToJson, E> {
+        Ok(self.tagged("i32", v))
+    }
+
+    fn visit_i64<E: de::Error>(self, v: i64) -> Result<ToJson, E> {
+        self.output
+            .write(|pending| write!(pending, "{}", v).expect("JSON"));
+
+        Ok(ToJson)
+    }
+
+    fn visit_u8<E: de::Error>(self, v: u8) -> Result<ToJson, E> {
+        Ok(self.tagged("u8", v))
+    }
+
+    fn visit_u16<E: de::Error>(self, v: u16) -> Result<ToJson, E> {
+        Ok(self.tagged("u16", v))
+    }
+
+    fn visit_u32<E: de::Error>(self, v: u32) -> Result<ToJson, E> {
+        Ok(self.tagged("u32", v))
+    }
+
+    fn visit_u64<E: de::Error>(self, v: u64) -> Result<ToJson, E> {
+        self.output.write(|pending| {
+            pending.extend_from_slice(b"{\"/u64\":");
+            write_wide(v, pending);
+            pending.put_u8(b'}');
+        });
+
+        Ok(ToJson)
+    }
+
+    fn visit_f32<E: de::Error>(self, v: f32) -> Result<ToJson, E> {
+        Ok(self.tagged_float("f32", v))
+    }
+
+    fn visit_f64<E: de::Error>(self, v: f64) -> Result<ToJson, E> {
+        if v.is_finite() {
+            self.output.write(|pending| write_float(v, pending));
+            Ok(ToJson)
+        } else {
+            Ok(self.tagged_float("f64", v))
+        }
+    }
+
+    async fn visit_array_bool<A: ArrayAccess<bool>>(self, a: A) -> Result<ToJson, A::Error> {
+        self.array("bool", a, |b, pending| {
+            pending.extend_from_slice(if b { b"true".as_slice() } else { b"false" })
+        })
+        .await
+    }
+
+    async fn visit_array_i8<A: ArrayAccess<i8>>(self, a: A) -> Result<ToJson, A::Error> {
+        self.array("i8", a, write_int).await
+    }
+
+    async fn visit_array_i16<A: ArrayAccess<i16>>(self, a: A) -> Result<ToJson, A::Error> {
+        self.array("i16", a, write_int).await
+    }
+
+    async fn visit_array_i32<A: ArrayAccess<i32>>(self, a: A) -> Result<ToJson, A::Error> {
+        self.array("i32", a, write_int).await
+    }
+
+    async fn visit_array_i64<A: ArrayAccess<i64>>(self, a: A) -> Result<ToJson, A::Error> {
+        self.array("i64", a, write_int).await
+    }
+
+    async fn visit_array_u8<A: ArrayAccess<u8>>(self, a: A) -> Result<ToJson, A::Error> {
+        self.array("u8", a, write_int).await
+    }
+
+    async fn visit_array_u16<A: ArrayAccess<u16>>(self, a: A) -> Result<ToJson, A::Error> {
+        self.array("u16", a, write_int).await
+    }
+
+    async fn visit_array_u32<A: ArrayAccess<u32>>(self, a: A) -> Result<ToJson, A::Error> {
+        self.array("u32", a, write_int).await
+    }
+
+    async fn visit_array_u64<A: ArrayAccess<u64>>(self, a: A) -> Result<ToJson, A::Error> {
+        self.array("u64", a, write_wide).await
+    }
+
+    async fn visit_array_f32<A: ArrayAccess<f32>>(self, a: A) -> Result<ToJson, A::Error> {
+        self.array("f32", a, write_float).await
+    }
+
+    async fn visit_array_f64<A: ArrayAccess<f64>>(self, a: A) -> Result<ToJson, A::Error> {
+        self.array("f64", a, write_float).await
+    }
+
+    fn visit_string<E: de::Error>(self, v: String) -> Result<ToJson, E> {
+        self.output.write(|pending| write_string(&v, pending));
+        Ok(ToJson)
+    }
+
+    fn visit_unit<E: de::Error>(self) -> Result<ToJson, E> {
+        self.output.write_slice(b"null");
+        Ok(ToJson)
+    }
+
+    fn visit_none<E: de::Error>(self) -> Result<ToJson, E> {
+        self.output.write_slice(b"null");
+        Ok(ToJson)
+    }
+
+    async fn visit_map<A: MapAccess>(self, mut access: A) -> Result<ToJson, A::Error> {
+        // whether this map is an object, which is only known once its first key is read
+        let mut is_object = None;
+
+        loop {
+            let key = Output::buffer();
+            if access.next_key::<ToJson>(key.clone()).await?.is_none() {
+                break;
+            }
+
+            let key = key.take();
+            let is_string = key.first() == Some(&b'"');
+
+            match is_object {
+                None if is_string => self.output.write_slice(b"{"),
+                None => self.output.write_slice(b"{\"/map\":["),
+                Some(is_object) if is_object == is_string => self.output.write_slice(b","),
+                Some(_) => {
+                    return Err(de::Error::custom(
+                        "cannot transcode a map with both string and non-string keys to JSON",
+                    ))
+                }
+            }
+
+            is_object = Some(is_string);
+
+            self.output.write(|pending| {
+                if is_string {
+                    if key.starts_with(b"\"/") {
+                        pending.extend_from_slice(b"\"/");
+                        pending.extend_from_slice(&key[1..]);
+                    } else {
+                        pending.extend_from_slice(&key);
+                    }
+
+                    pending.put_u8(b':');
+                } else {
+                    pending.put_u8(b'[');
+                    pending.extend_from_slice(&key);
+                    pending.put_u8(b',');
+                }
+            });
+
+            access.next_value::<ToJson>(self.output.clone()).await?;
+
+            if !is_string {
+                self.output.write_slice(b"]");
+            }
+
+            self.output.flush().await?;
+        }
+
+        match is_object {
+            None => self.output.write_slice(b"{}"),
+            Some(true) => self.output.write_slice(b"}"),
+            Some(false) => self.output.write_slice(b"]}"),
+        }
+
+        Ok(ToJson)
+    }
+
+    async fn visit_seq<A: SeqAccess>(self, mut access: A) -> Result<ToJson, A::Error> {
+        self.output.write_slice(b"[");
+
+        let mut first = true;
+        loop {
+            if first {
+                first = false;
+            } else {
+                self.output.write_slice(b",");
+            }
+
+            // if there is no next element, this separator is removed below
+            if access
+                .next_element::<ToJson>(self.output.clone())
+                .await?
+                .is_none()
+            {
+                break;
+            }
+
+            self.output.flush().await?;
+        }
+
+        self.output.write(|pending| {
+            if pending.last() == Some(&b',') {
+                pending.truncate(pending.len() - 1);
+            }
+
+            pending.put_u8(b']');
+        });
+
+        Ok(ToJson)
+    }
+}
+
+impl VisitNative for ToJsonVisitor {
+    fn visit_native(self, native: Native) -> ToJson {
+        fn write_array<T: Copy>(array: &[T], write: fn(T, &mut BytesMut), pending: &mut BytesMut) {
+            pending.put_u8(b'[');
+
+            for (i, element) in array.iter().enumerate() {
+                if i > 0 {
+                    pending.put_u8(b',');
+                }
+
+                write(*element, pending);
+            }
+
+            pending.put_u8(b']');
+        }
+
+        let dtype = match &native {
+            Native::I128(_) | Native::ArrayI128(_) => "i128",
+            Native::U128(_) | Native::ArrayU128(_) => "u128",
+            Native::Uuid(_) => "uuid",
+            #[cfg(feature = "half")]
+            Native::F16(_) | Native::ArrayF16(_) => "f16",
+            #[cfg(feature = "half")]
+            Native::BF16(_) | Native::ArrayBF16(_) => "bf16",
+        };
+
+        self.output.write(|pending| {
+            write!(pending, "{{\"/{}\":", dtype).expect("JSON");
+
+            match native {
+                Native::I128(i) => write_wide(i, pending),
+                Native::U128(u) => write_wide(u, pending),
+                Native::ArrayI128(array) => write_array(&array, write_wide, pending),
+                Native::ArrayU128(array) => write_array(&array, write_wide, pending),
+                Native::Uuid(uuid) => write!(pending, "\"{}\"", uuid).expect("JSON"),
+                #[cfg(feature = "half")]
+                Native::F16(f) => write_float(f.to_f32(), pending),
+                #[cfg(feature = "half")]
+                Native::BF16(f) => write_float(f.to_f32(), pending),
+                #[cfg(feature = "half")]
+                Native::ArrayF16(array) => write_array(
+                    &array,
+                    |f, pending| write_float(f.to_f32(), pending),
+                    pending,
+                ),
+                #[cfg(feature = "half")]
+                Native::ArrayBF16(array) => write_array(
+                    &array,
+                    |f, pending| write_float(f.to_f32(), pending),
+                    pending,
+                ),
+            }
+
+            pending.put_u8(b'}');
+        });
+
+        ToJson
+    }
+}
+
+fn write_int<N: fmt::Display>(n: N, pending: &mut BytesMut) {
+    write!(pending, "{}", n).expect("JSON")
+}
+
+/// Write an integer which may be too large to be an `i64`, in which case it's written as a string.
+fn write_wide<N: Copy + fmt::Display>(n: N, pending: &mut BytesMut)
+where
+    i64: TryFrom<N>,
+{
+    if i64::try_from(n).is_ok() {
+        write!(pending, "{}", n).expect("JSON")
+    } else {
+        write!(pending, "\"{}\"", n).expect("JSON")
+    }
+}
+
+fn write_float<F: Float + fmt::Debug>(f: F, pending: &mut BytesMut) {
+    if f.is_finite() {
+        // the debug representation always includes a decimal point or an exponent
+        write!(pending, "{:?}", f).expect("JSON")
+    } else if f.is_nan() {
+        pending.extend_from_slice(b"\"NaN\"")
+    } else if f.is_sign_positive() {
+        pending.extend_from_slice(b"\"inf\"")
+    } else {
+        pending.extend_from_slice(b"\"-inf\"")
+    }
+}
+
+fn write_string(s: &str, pending: &mut BytesMut) {
+    pending.put_u8(b'"');
+
+    for c in s.chars() {
+        match c {
+            '"' => pending.extend_from_slice(b"\\\""),
+            '\\' => pending.extend_from_slice(b"\\\\"),
+            '\n' => pending.extend_from_slice(b"\\n"),
+            '\r' => pending.extend_from_slice(b"\\r"),
+            '\t' => pending.extend_from_slice(b"\\t"),
+            c if c < ' ' => write!(pending, "\\u{:04x}", c as u32).expect("JSON"),
+            c => {
+                let mut buf = [0u8; 4];
+                pending.extend_from_slice(c.encode_utf8(&mut buf).as_bytes())
+            }
+        }
+    }
+
+    pending.put_u8(b'"');
+}
+
+/// A JSON value which has been transcoded to TBON
+struct FromJson;
+
+impl FromStream for FromJson {
+    type Context = Output<EncodeError>;
+
+    async fn from_stream<D: de::Decoder>(
+        output: Output<EncodeError>,
+        decoder: &mut D,
+    ) -> Result<Self, D::Error> {
+        decoder.decode_any(FromJsonVisitor { output }).await
+    }
+}
+
+struct FromJsonVisitor {
+    output: Output<EncodeError>,
+}
+
+impl FromJsonVisitor {
+    fn scalar(self, dtype: Type, value: &[u8]) -> FromJson {
+        self.output.write(|pending| {
+            pending.put_u8(dtype.to_u8().expect("type bit"));
+            pending.extend_from_slice(value);
+        });
+
+        FromJson
+    }
+}
+
+impl Visitor for FromJsonVisitor {
+    type Value = FromJson;
+
+    fn expecting() -> &'static str {
+        "a JSON value"
+    }
+
+    fn visit_bool<E: de::Error>(self, v: bool) -> Result<FromJson, E> {
+        let value = if v { TRUE } else { FALSE };
+        Ok(self.scalar(Type::Bool, value))
+    }
+
+    fn visit_i64<E: de::Error>(self, v: i64) -> Result<FromJson, E> {
+        Ok(self.scalar(Type::I64, &v.to_be_bytes()))
+    }
+
+    fn visit_u64<E: de::Error>(self, v: u64) -> Result<FromJson, E> {
+        Ok(self.scalar(Type::U64, &v.to_be_bytes()))
+    }
+
+    fn visit_f64<E: de::Error>(self, v: f64) -> Result<FromJson, E> {
+        Ok(self.scalar(Type::F64, &v.to_be_bytes()))
+    }
+
+    fn visit_string<E: de::Error>(self, v: String) -> Result<FromJson, E> {
+        self.output.write(|pending| write_tbon_string(&v, pending));
+        Ok(FromJson)
+    }
+
+    fn visit_unit<E: de::Error>(self) -> Result<FromJson, E> {
+        Ok(self.scalar(Type::None, &[]))
+    }
+
+    fn visit_none<E: de::Error>(self) -> Result<FromJson, E> {
+        Ok(self.scalar(Type::None, &[]))
+    }
+
+    async fn visit_map<A: MapAccess>(self, mut access: A) -> Result<FromJson, A::Error> {
+        let key = match access.next_key::<String>(()).await? {
+            Some(key) => key,
+            None => {
+                self.output.write_slice(b"{}");
+                return Ok(FromJson);
+            }
+        };
+
+        if key.starts_with('/') && !key.starts_with("//") {
+            let context = (key, self.output.clone());
+            let Tagged { tag } = access.next_value::<Tagged>(context).await?;
+
+            return match access.next_key::<IgnoredAny>(()).await? {
+                None => Ok(FromJson),
+                Some(_) => Err(de::Error::custom(format!(
+                    "an object tagged {} must not have any other entries",
+                    tag
+                ))),
+            };
+        }
+
+        self.output.write_slice(MAP_BEGIN);
+
+        let mut key = Some(key);
+        while let Some(next) = key {
+            let next = match next.strip_prefix('/') {
+                Some(unescaped) if unescaped.starts_with('/') => unescaped,
+                _ => &next,
+            };
+
+            self.output
+                .write(|pending| write_tbon_string(next, pending));
+            access.next_value::<FromJson>(self.output.clone()).await?;
+            self.output.flush().await?;
+
+            key = access.next_key::<String>(()).await?;
+        }
+
+        self.output.write_slice(MAP_END);
+        Ok(FromJson)
+    }
+
+    async fn visit_seq<A: SeqAccess>(self, mut access: A) -> Result<FromJson, A::Error> {
+        self.output.write_slice(LIST_BEGIN);
+
+        while access
+            .next_element::<FromJson>(self.output.clone())
+            .await?
+            .is_some()
+        {
+            self.output.flush().await?;
+        }
+
+        self.output.write_slice(LIST_END);
+        Ok(FromJson)
+    }
+}
+
+fn write_tbon_string(s: &str, pending: &mut BytesMut) {
+    pending.extend_from_slice(STRING_DELIMIT);
+    escape(
+        s.as_bytes(),
+        &[STRING_DELIMIT[0], STRING_DELIMIT[0]],
+        pending,
+    );
+    pending.extend_from_slice(STRING_DELIMIT);
+}
+
+/// The value of a JSON object with a type tag, which has been transcoded to TBON
+struct Tagged {
+    tag: String,
+}
+
+impl FromStream for Tagged {
+    type Context = (String, Output<EncodeError>);
+
+    async fn from_stream<D: de::Decoder>(
+        context: (String, Output<EncodeError>),
+        decoder: &mut D,
+    ) -> Result<Self, D::Error> {
+        let (tag, output) = context;
+
+        let dtype = if tag == MAP_TAG {
+            None
+        } else {
+            match dtype(&tag) {
+                Some(dtype) => Some(dtype),
+                None => return Err(de::Error::custom(format!("unknown type tag {}", tag))),
+            }
+        };
+
+        decoder.decode_any(TaggedVisitor { dtype, output }).await?;
+        Ok(Tagged { tag })
+    }
+}
+
+/// Parse the type of a tag like `/u16`.
+fn dtype(tag: &str) -> Option<Type> {
+    match tag {
+        "/bool" => Some(Type::Bool),
+        #[cfg(feature = "half")]
+        "/f16" => Some(Type::F16),
+        #[cfg(feature = "half")]
+        "/bf16" => Some(Type::BF16),
+        "/f32" => Some(Type::F32),
+        "/f64" => Some(Type::F64),
+        "/i8" => Some(Type::I8),
+        "/i16" => Some(Type::I16),
+        "/i32" => Some(Type::I32),
+        "/i64" => Some(Type::I64),
+        "/i128" => Some(Type::I128),
+        "/u8" => Some(Type::U8),
+        "/u16" => Some(Type::U16),
+        "/u32" => Some(Type::U32),
+        "/u64" => Some(Type::U64),
+        "/u128" => Some(Type::U128),
+        "/uuid" => Some(Type::Uuid),
+        _ => None,
+    }
+}
+
+/// A JSON scalar, to be transcoded to a TBON number of a specific type
+enum Scalar {
+    Bool(bool),
+    Int(i64),
+    UInt(u64),
+    Float(f64),
+    String(String),
+}
+
+impl Scalar {
+    /// Encode this [`Scalar`] as an element of the given `dtype`.
+    fn encode(self, dtype: &Type, pending: &mut BytesMut) -> Result<(), String> {
+        match (dtype, self) {
+            (Type::Bool, Self::Bool(b)) => pending.put_u8(b as u8),
+            #[cfg(feature = "half")]
+            (Type::F16, scalar) => pending.put_u16(half::f16::from_f64(scalar.float()?).to_bits()),
+            #[cfg(feature = "half")]
+            (Type::BF16, scalar) => {
+                pending.put_u16(half::bf16::from_f64(scalar.float()?).to_bits())
+            }
+            (Type::F32, scalar) => pending.put_f32(scalar.float()? as f32),
+            (Type::F64, scalar) => pending.put_f64(scalar.float()?),
+            (Type::I8, scalar) => pending.put_i8(scalar.int()?),
+            (Type::I16, scalar) => pending.put_i16(scalar.int()?),
+            (Type::I32, scalar) => pending.put_i32(scalar.int()?),
+            (Type::I64, scalar) => pending.put_i64(scalar.int()?),
+            (Type::I128, scalar) => pending.put_i128(scalar.int()?),
+            (Type::U8, scalar) => pending.put_u8(scalar.int()?),
+            (Type::U16, scalar) => pending.put_u16(scalar.int()?),
+            (Type::U32, scalar) => pending.put_u32(scalar.int()?),
+            (Type::U64, scalar) => pending.put_u64(scalar.int()?),
+            (Type::U128, scalar) => pending.put_u128(scalar.int()?),
+            (Type::Uuid, Self::String(s)) => match Uuid::parse_str(&s) {
+                Ok(uuid) => pending.extend_from_slice(uuid.as_bytes()),
+                Err(_) => return Err(format!("expected a UUID but found {:?}", s)),
+            },
+            (dtype, scalar) => return Err(format!("expected a {} but found {}", dtype, scalar)),
+        }
+
+        Ok(())
+    }
+
+    fn float(self) -> Result<f64, String> {
+        match self {
+            Self::Float(f) => Ok(f),
+            Self::Int(i) => Ok(i as f64),
+            Self::UInt(u) => Ok(u as f64),
+            Self::String(s) if s == "NaN" => Ok(f64::NAN),
+            Self::String(s) if s == "inf" => Ok(f64::INFINITY),
+            Self::String(s) if s == "-inf" => Ok(f64::NEG_INFINITY),
+            other => Err(format!("expected a float but found {}", other)),
+        }
+    }
+
+    fn int<T: TryFrom<i64> + TryFrom<u64> + FromStr>(self) -> Result<T, String> {
+        match self {
+            Self::Int(i) => T::try_from(i).map_err(|_| format!("{} is out of range", i)),
+            Self::UInt(u) => T::try_from(u).map_err(|_| format!("{} is out of range", u)),
+            Self::String(s) => match s.parse::<u64>() {
+                Ok(u) => T::try_from(u).map_err(|_| format!("{} is out of range", u)),
+                // a 128-bit integer which is too large to be a u64
+                Err(_) => s
+                    .parse()
+                    .map_err(|_| format!("expected an integer but found {:?}", s)),
+            },
+            other => Err(format!("expected an integer but found {}", other)),
+        }
+    }
+}
+
+impl fmt::Display for Scalar {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Bool(b) => fmt::Display::fmt(b, f),
+            Self::Int(i) => fmt::Display::fmt(i, f),
+            Self::UInt(u) => fmt::Display::fmt(u, f),
+            Self::Float(n) => fmt::Display::fmt(n, f),
+            Self::String(s) => fmt::Debug::fmt(s, f),
+        }
+    }
+}
+
+impl FromStream for Scalar {
+    type Context = ();
+
+    async fn from_stream<D: de::Decoder>(_: (), decoder: &mut D) -> Result<Self, D::Error> {
+        decoder.decode_any(ScalarVisitor).await
+    }
+}
+
+struct ScalarVisitor;
+
+impl Visitor for ScalarVisitor {
+    type Value = Scalar;
+
+    fn expecting() -> &'static str {
+        "a JSON boolean, number, or string"
+    }
+
+    fn visit_bool<E: de::Error>(self, v: bool) -> Result<Scalar, E> {
+        Ok(Scalar::Bool(v))
+    }
+
+    fn visit_i64<E: de::Error>(self, v: i64) -> Result<Scalar, E> {
+        Ok(Scalar::Int(v))
+    }
+
+    fn visit_u64<E: de::Error>(self, v: u64) -> Result<Scalar, E> {
+        Ok(Scalar::UInt(v))
+    }
+
+    fn visit_f64<E: de::Error>(self, v: f64) -> Result<Scalar, E> {
+        Ok(Scalar::Float(v))
+    }
+
+    fn visit_string<E: de::Error>(self, v: String) -> Result<Scalar, E> {
+        Ok(Scalar::String(v))
+    }
+}
+
+/// A `[key, value]` entry of a map tagged `/map`, which has been transcoded to TBON
+struct Entry;
+
+impl FromStream for Entry {
+    type Context = Output<EncodeError>;
+
+    async fn from_stream<D: de::Decoder>(
+        output: Output<EncodeError>,
+        decoder: &mut D,
+    ) -> Result<Self, D::Error> {
+        decoder.decode_seq(EntryVisitor { output }).await
+    }
+}
+
+struct EntryVisitor {
+    output: Output<EncodeError>,
+}
+
+impl Visitor for EntryVisitor {
+    type Value = Entry;
+
+    fn expecting() -> &'static str {
+        "a [key, value] map entry"
+    }
+
+    async fn visit_seq<A: SeqAccess>(self, mut access: A) -> Result<Entry, A::Error> {
+        for _ in 0..2 {
+            if access
+                .next_element::<FromJson>(self.output.clone())
+                .await?
+                .is_none()
+            {
+                return Err(de::Error::invalid_length(1, Self::expecting()));
+            }
+        }
+
+        match access.next_element::<IgnoredAny>(()).await? {
+            None => Ok(Entry),
+            Some(_) => Err(de::Error::invalid_length(3, Self::expecting())),
+        }
+    }
+}
+
+struct TaggedVisitor {
+    /// The type of the tagged value, or `None` if it's a map
+    dtype: Option<Type>,
+    output: Output<EncodeError>,
+}
+
+impl TaggedVisitor {
+    fn scalar<E: de::Error>(self, scalar: Scalar) -> Result<(), E> {
+        let dtype = match self.dtype {
+            Some(dtype) => dtype,
+            None => return Err(de::Error::custom("expected a list of map entries")),
+        };
+
+        let mut pending = self.output.pending.lock().expect("pending output");
+        pending.put_u8(dtype.to_u8().expect("type bit"));
+        scalar
+            .encode(&dtype, &mut pending)
+            .map_err(de::Error::custom)
+    }
+}
+
+impl Visitor for TaggedVisitor {
+    type Value = ();
+
+    fn expecting() -> &'static str {
+        "a tagged value"
+    }
+
+    fn visit_bool<E: de::Error>(self, v: bool) -> Result<(), E> {
+        self.scalar(Scalar::Bool(v))
+    }
+
+    fn visit_i64<E: de::Error>(self, v: i64) -> Result<(), E> {
+        self.scalar(Scalar::Int(v))
+    }
+
+    fn visit_u64<E: de::Error>(self, v: u64) -> Result<(), E> {
+        self.scalar(Scalar::UInt(v))
+    }
+
+    fn visit_f64<E: de::Error>(self, v: f64) -> Result<(), E> {
+        self.scalar(Scalar::Float(v))
+    }
+
+    fn visit_string<E: de::Error>(self, v: String) -> Result<(), E> {
+        self.scalar(Scalar::String(v))
+    }
+
+    async fn visit_seq<A: SeqAccess>(self, mut access: A) -> Result<(), A::Error> {
+        let dtype = match self.dtype {
+            Some(dtype) => dtype,
+            None => {
+                self.output.write_slice(MAP_BEGIN);
+
+                while access
+                    .next_element::<Entry>(self.output.clone())
+                    .await?
+                    .is_some()
+                {
+                    self.output.flush().await?;
+                }
+
+                self.output.write_slice(MAP_END);
+                return Ok(());
+            }
+        };
+
+        if matches!(dtype, Type::Uuid) {
+            return Err(de::Error::custom("expected a UUID string"));
+        }
+
+        self.output.write(|pending| {
+            pending.extend_from_slice(ARRAY_DELIMIT);
+            pending.put_u8(dtype.to_u8().expect("type bit"));
+        });
+
+        let mut element = BytesMut::with_capacity(8);
+        while let Some(scalar) = access.next_element::<Scalar>(()).await? {
+            scalar
+                .encode(&dtype, &mut element)
+                .map_err(de::Error::custom)?;
+
+            self.output
+                .write(|pending| escape(&element, ARRAY_DELIMIT, pending));
+
+            element.clear();
+            self.output.flush().await?;
+        }
+
+        self.output.write_slice(ARRAY_DELIMIT);
+        Ok(())
+    }
+}
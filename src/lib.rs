@@ -8,14 +8,36 @@
 //! let actual = block_on(tbon::de::try_decode((), stream)).unwrap();
 //! assert_eq!(expected, actual);
 //! ```
+//!
+//! Values which are already in memory can also be encoded and decoded without an async runtime:
+//! ```
+//! let expected = ("one".to_string(), 2.0, vec![3, 4], vec![5u8]);
+//! let encoded = tbon::to_vec(&expected).unwrap();
+//! let actual = tbon::from_slice((), &encoded).unwrap();
+//! assert_eq!(expected, actual);
+//! ```
 
 use element::Element;
 
 mod constants;
 mod element;
+mod hook;
 
+#[cfg(feature = "tokio-codec")]
+pub mod codec;
 pub mod de;
+pub mod debug;
 pub mod en;
+#[cfg(feature = "json")]
+pub mod json;
+#[cfg(feature = "serde")]
+pub mod serde;
+mod value;
+mod varint;
+
+pub use de::{from_bytes, from_reader, from_slice};
+pub use en::{to_vec, to_writer};
+pub use value::{Array, Value, ValueIndex};
 
 #[cfg(test)]
 mod tests {
@@ -227,8 +249,1773 @@ mod tests {
         run_test(Bytes::from(vec![1, 2, 3])).await;
     }
 
+    #[tokio::test]
+    async fn test_limits() {
+        async fn recode<'en, T>(value: T, options: DecoderOptions) -> Result<T, super::de::Error>
+        where
+            T: FromStream<Context = ()> + IntoStream<'en> + 'en,
+        {
+            let encoded = encode(value).unwrap();
+            try_decode_with_options((), encoded, options).await
+        }
+
+        let nested = vec![vec![vec![1u8]]];
+        let options = DecoderOptions::new().max_depth(3);
+        assert_eq!(recode(nested.clone(), options).await.unwrap(), nested);
+        let options = DecoderOptions::new().max_depth(2);
+        let error = recode(nested, options).await.unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::LimitExceeded);
+
+        let string = "hello world".to_string();
+        let options = DecoderOptions::new().max_string_len(11);
+        assert_eq!(recode(string.clone(), options).await.unwrap(), string);
+        let options = DecoderOptions::new().max_string_len(10);
+        assert!(recode(string, options).await.is_err());
+
+        let list = vec![1u64; 100];
+        let options = DecoderOptions::new().max_array_len(100);
+        assert_eq!(recode(list.clone(), options).await.unwrap(), list);
+        let options = DecoderOptions::new().max_array_len(99);
+        assert!(recode(list, options).await.is_err());
+
+        let bytes = Bytes::from(vec![2u8; 100]);
+        let options = DecoderOptions::new().max_array_len(99);
+        assert!(recode(bytes.clone(), options).await.is_err());
+        let options = DecoderOptions::new().max_bytes(100);
+        assert!(recode(bytes, options).await.is_err());
+
+        let map: BTreeMap<u8, bool> = (0..10).map(|i| (i, i % 2 == 0)).collect();
+        let options = DecoderOptions::new().max_map_entries(10);
+        assert_eq!(recode(map.clone(), options).await.unwrap(), map);
+        let options = DecoderOptions::new().max_map_entries(9);
+        assert!(recode(map, options).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_error_kind() {
+        async fn decode_bytes<T: FromStream<Context = ()>>(
+            bytes: &'static [u8],
+        ) -> Result<T, super::de::Error> {
+            let source = futures::stream::once(future::ready(Bytes::from_static(bytes)));
+            decode((), source).await
+        }
+
+        // a u64 which is missing its last byte
+        let error = decode_bytes::<u64>(&[12, 0, 0, 0, 0, 0, 0, 0])
+            .await
+            .unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::UnexpectedEnd);
+        assert_eq!(error.offset(), Some(8));
+
+        // an f64 where a u64 is expected
+        let error = decode_bytes::<Vec<u64>>(b"[\x0c\0\0\0\0\0\0\0\x01\x04")
+            .await
+            .unwrap_err();
+
+        assert_eq!(error.kind(), ErrorKind::InvalidType);
+        assert_eq!(error.offset(), Some(10));
+
+        let error = decode_bytes::<Vec<u64>>(b"[\xff").await.unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::UnknownTypeBit);
+        assert_eq!(error.offset(), Some(1));
+
+        let error = decode_bytes::<Vec<u64>>(b"{}").await.unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::UnexpectedDelimiter);
+        assert_eq!(error.offset(), Some(0));
+
+        let error = decode_bytes::<String>(b"\"\xff\xfe\"").await.unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::InvalidUtf8);
+        assert_eq!(error.offset(), Some(0));
+
+        let error = decode_bytes::<(u8, u8)>(b"[\x09\x01]").await.unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::InvalidLength);
+
+        #[derive(Debug)]
+        struct Even;
+
+        impl FromStream for Even {
+            type Context = ();
+
+            async fn from_stream<D: destream::de::Decoder>(
+                cxt: (),
+                decoder: &mut D,
+            ) -> Result<Self, D::Error> {
+                let n = u8::from_stream(cxt, decoder).await?;
+                if n % 2 == 0 {
+                    Ok(Even)
+                } else {
+                    Err(destream::de::Error::custom(format!("{} is odd", n)))
+                }
+            }
+        }
+
+        let error = decode_bytes::<Vec<Even>>(b"[\x09\x02\x09\x03]")
+            .await
+            .unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::Custom);
+        assert_eq!(error.offset(), Some(3));
+
+        let source =
+            futures::stream::iter(vec![Ok(Bytes::from_static(b"[")), Err("connection reset")]);
+
+        let error = try_decode::<_, _, Vec<u8>>((), source).await.unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::Io);
+        assert_eq!(error.offset(), Some(1));
+    }
+
+    #[tokio::test]
+    async fn test_error_path() {
+        let valid = BTreeMap::from_iter(vec![
+            ("bias".to_string(), vec![0i32]),
+            ("weights".to_string(), vec![1i32, 2]),
+        ]);
+
+        let invalid = BTreeMap::from_iter(vec![
+            ("bias".to_string(), vec![0.]),
+            ("weights".to_string(), vec![1.5f64]),
+        ]);
+
+        let encoded = encode((&valid, &valid, &invalid)).unwrap();
+        let error = try_decode::<_, _, Vec<BTreeMap<String, Vec<i32>>>>((), encoded)
+            .await
+            .unwrap_err();
+
+        assert_eq!(error.kind(), ErrorKind::InvalidType);
+        assert_eq!(error.path(), Some("[2].{\"bias\"}[0]"));
+        assert!(error
+            .to_string()
+            .starts_with("invalid type: 64-bit float, expected 32-bit int at [2].{\"bias\"}[0]"));
+
+        let map: HashMap<u64, Vec<&str>> = HashMap::from_iter(vec![(7, vec!["seven"])]);
+        let encoded = encode(map).unwrap();
+        let error = try_decode::<_, _, HashMap<u64, Vec<u64>>>((), encoded)
+            .await
+            .unwrap_err();
+
+        assert_eq!(error.path(), Some("{7}[0]"));
+
+        let error = try_decode::<_, _, u64>((), encode(1.0f32).unwrap())
+            .await
+            .unwrap_err();
+
+        assert_eq!(error.path(), None);
+    }
+
+    #[tokio::test]
+    async fn test_ignore_value() {
+        async fn skip<'en, T: IntoStream<'en> + 'en>(value: T) {
+            let encoded = encode((value, "after")).unwrap();
+            let (_, after): (destream::IgnoredAny, String) = try_decode((), encoded).await.unwrap();
+
+            assert_eq!(after, "after");
+        }
+
+        skip(()).await;
+        skip(Some(1u8)).await;
+        skip(true).await;
+        skip(-93i8).await;
+        skip(0x5d5d5d5d5d5d5d5du64).await;
+        skip(f64::MAX).await;
+        skip("a \"string\" with ] and } and \\").await;
+        skip(vec![vec![93i32, 125], vec![], vec![0x5d7d5b7b]]).await;
+        skip(Bytes::from(b"=\\]}=".to_vec())).await;
+        skip(Bytes::from(vec![b'\\'; 10_000])).await;
+        skip(vec![String::from("]"), String::from("\\")]).await;
+
+        let mut map = BTreeMap::new();
+        map.insert("[".to_string(), vec![BTreeMap::<u16, Vec<f32>>::new()]);
+        map.insert(
+            "}".to_string(),
+            vec![BTreeMap::from_iter(vec![(
+                0x7d5d,
+                vec![f32::from_bits(0x5d5d7d7d)],
+            )])],
+        );
+
+        skip(map).await;
+
+        let encoded = encode((vec![1u8], "after")).unwrap();
+        let options = DecoderOptions::new().max_depth(1);
+        let result: Result<(destream::IgnoredAny, String), _> =
+            try_decode_with_options((), encoded, options).await;
+
+        assert!(result.is_err());
+
+        let truncated = futures::stream::once(future::ready(Bytes::from_static(b"[[\x09\x01")));
+        let result: Result<(destream::IgnoredAny,), _> = decode((), truncated).await;
+        assert_eq!(result.unwrap_err().kind(), ErrorKind::UnexpectedEnd);
+    }
+
+    #[test]
+    fn test_blocking() {
+        let value = (
+            "hello".to_string(),
+            vec![1u64, 2, 3],
+            BTreeMap::from_iter(vec![(-1i8, Bytes::from(vec![b'=', b'\\']))]),
+        );
+
+        let encoded = super::to_vec(&value).unwrap();
+        let decoded: (String, Vec<u64>, BTreeMap<i8, Bytes>) =
+            super::from_slice((), &encoded).unwrap();
+
+        assert_eq!(decoded, value);
+
+        let decoded: (String, Vec<u64>, BTreeMap<i8, Bytes>) =
+            super::from_bytes((), Bytes::from(encoded.clone())).unwrap();
+
+        assert_eq!(decoded, value);
+
+        let mut written = Vec::new();
+        super::to_writer(&mut written, &value).unwrap();
+        assert_eq!(written, encoded);
+
+        let list: Vec<u32> = (0..1_000).collect();
+        let encoded = super::to_vec(&list).unwrap();
+        let decoded: Vec<u32> = super::from_reader((), encoded.as_slice()).unwrap();
+        assert_eq!(decoded, list);
+
+        let error = super::from_slice::<Vec<u32>>((), &encoded[..100]).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::UnexpectedEnd);
+    }
+
+    #[cfg(feature = "tokio-io")]
+    #[tokio::test]
+    async fn test_write_to() {
+        use std::pin::Pin;
+        use std::task::{Context, Poll};
+
+        #[derive(Default)]
+        struct Writes {
+            writes: Vec<Vec<u8>>,
+            flushes: usize,
+        }
+
+        impl tokio::io::AsyncWrite for Writes {
+            fn poll_write(
+                mut self: Pin<&mut Self>,
+                _cx: &mut Context<'_>,
+                buf: &[u8],
+            ) -> Poll<std::io::Result<usize>> {
+                self.writes.push(buf.to_vec());
+                Poll::Ready(Ok(buf.len()))
+            }
+
+            fn poll_flush(
+                mut self: Pin<&mut Self>,
+                _cx: &mut Context<'_>,
+            ) -> Poll<std::io::Result<()>> {
+                self.flushes += 1;
+                Poll::Ready(Ok(()))
+            }
+
+            fn poll_shutdown(
+                self: Pin<&mut Self>,
+                _cx: &mut Context<'_>,
+            ) -> Poll<std::io::Result<()>> {
+                Poll::Ready(Ok(()))
+            }
+        }
+
+        let list: Vec<u32> = (0..100).collect();
+        let expected = super::to_vec(&list).unwrap();
+
+        let mut writer = Writes::default();
+        write_to(&mut writer, &list).await.unwrap();
+        assert_eq!(writer.writes.concat(), expected);
+        assert_eq!(writer.writes.len(), 1);
+        assert_eq!(writer.flushes, 1);
+
+        let options = WriteOptions::new().buffer_size(64);
+        let mut writer = Writes::default();
+        write_to_with_options(&mut writer, &list, options)
+            .await
+            .unwrap();
+        assert_eq!(writer.writes.concat(), expected);
+        assert!(writer.writes.iter().rev().skip(1).all(|w| w.len() >= 64));
+
+        let options = options.flush_each_write(true);
+        let mut writer = Writes::default();
+        write_seq_to_with_options(&mut writer, futures::stream::iter(list.clone()), options)
+            .await
+            .unwrap();
+
+        assert_eq!(writer.writes.concat(), expected);
+        assert_eq!(writer.flushes, writer.writes.len() + 1);
+
+        let map = BTreeMap::from_iter(vec![("one".to_string(), 1u8), ("two".to_string(), 2)]);
+        let mut written = Vec::new();
+        write_map_to(&mut written, futures::stream::iter(map.clone()))
+            .await
+            .unwrap();
+
+        let decoded: BTreeMap<String, u8> = read_from((), written.as_slice()).await.unwrap();
+        assert_eq!(decoded, map);
+
+        // the encoder options apply to the written stream
+        let encoder = EncoderOptions::new()
+            .magic_header(b"TBON")
+            .canonical_maps(true);
+        let options = WriteOptions::new().encoder_options(encoder);
+        let entries = vec![("two".to_string(), 2u8), ("one".to_string(), 1)];
+
+        let mut written = Vec::new();
+        write_map_to_with_options(
+            &mut written,
+            futures::stream::iter(entries.clone()),
+            options,
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(written, to_vec_with_options(&map, encoder).unwrap());
+        assert!(written.starts_with(b"TBON"));
+        // the entries were given out of order, so this also checks that the map is canonical
+        let decoder = DecoderOptions::new().magic_header(b"TBON");
+        let decoded: BTreeMap<String, u8> = from_slice_with_options((), &written, decoder).unwrap();
+        assert_eq!(decoded, map);
+
+        let mut written = Vec::new();
+        write_to_with_options(&mut written, &list, options)
+            .await
+            .unwrap();
+        assert_eq!(written, to_vec_with_options(&list, encoder).unwrap());
+
+        let mut written = Vec::new();
+        write_seq_to_with_options(&mut written, futures::stream::iter(list.clone()), options)
+            .await
+            .unwrap();
+        assert_eq!(written, to_vec_with_options(&list, encoder).unwrap());
+    }
+
+    #[cfg(feature = "futures-io")]
+    #[test]
+    fn test_futures_io() {
+        use futures::executor::block_on;
+
+        let value = (
+            "hello".to_string(),
+            vec![1u64, 2, 3],
+            BTreeMap::from_iter(vec![(-1i8, Bytes::from(vec![b'=', b'\\']))]),
+        );
+
+        let mut written = Vec::new();
+        block_on(write_to_futures(&mut written, &value)).unwrap();
+        assert_eq!(written, super::to_vec(&value).unwrap());
+
+        let decoded: (String, Vec<u64>, BTreeMap<i8, Bytes>) =
+            block_on(read_from_futures((), written.as_slice())).unwrap();
+
+        assert_eq!(decoded, value);
+
+        let options = WriteOptions::new().buffer_size(16);
+        let mut written = futures::io::Cursor::new(Vec::new());
+        let list = futures::stream::iter(0..1_000u32);
+        block_on(write_seq_to_futures_with_options(
+            &mut written,
+            list,
+            options,
+        ))
+        .unwrap();
+
+        let decoded: Vec<u32> =
+            block_on(read_from_futures((), written.into_inner().as_slice())).unwrap();
+
+        assert_eq!(decoded, (0..1_000).collect::<Vec<u32>>());
+
+        let encoder = EncoderOptions::new().magic_header(b"TBON");
+        let options = options.encoder_options(encoder);
+        let mut written = Vec::new();
+        block_on(write_to_futures_with_options(&mut written, &value, options)).unwrap();
+        assert_eq!(written, to_vec_with_options(&value, encoder).unwrap());
+    }
+
+    #[cfg(feature = "tokio-codec")]
+    #[tokio::test]
+    async fn test_codec() {
+        use futures::{SinkExt, StreamExt};
+        use tokio_util::codec::{Decoder as _, Framed};
+
+        use super::codec::TbonCodec;
+
+        type Message = (String, Vec<u64>, Option<Bytes>);
+
+        let messages: Vec<Message> = (0..10u64)
+            .map(|i| {
+                let bytes = if i % 2 == 0 {
+                    Some(Bytes::from(vec![b'='; i as usize]))
+                } else {
+                    None
+                };
+
+                (format!("message \"{}\"", i), (0..i).collect(), bytes)
+            })
+            .collect();
+
+        // a small buffer size splits each message across several reads
+        let (client, server) = tokio::io::duplex(7);
+        let mut client = Framed::new(client, TbonCodec::<Message>::new());
+        let mut server = Framed::new(server, TbonCodec::<Message>::new());
+
+        let expected = messages.clone();
+        let sender = tokio::spawn(async move {
+            for message in expected {
+                client.send(message).await.unwrap();
+            }
+        });
+
+        for message in &messages {
+            let received = server.next().await.unwrap().unwrap();
+            assert_eq!(&received, message);
+        }
+
+        sender.await.unwrap();
+        assert!(server.next().await.is_none());
+
+        // a value which is cut off by the end of the stream is an error
+        let encoded = super::to_vec(&messages[4]).unwrap();
+        let mut codec = TbonCodec::<Message>::new();
+        let mut src = bytes::BytesMut::from(&encoded[..encoded.len() / 2]);
+        assert!(codec.decode(&mut src).unwrap().is_none());
+
+        let error = codec.decode_eof(&mut src).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::UnexpectedEnd);
+
+        // a value is decoded once its last byte is received, using the given options
+        let encoder_options = EncoderOptions::new()
+            .compact_integers(true)
+            .length_prefixed_arrays(true)
+            .magic_header(b"TBON");
+
+        let decoder_options = DecoderOptions::new().magic_header(b"TBON");
+        let mut codec = TbonCodec::<Message>::with_options((), encoder_options, decoder_options);
+
+        let mut encoded = bytes::BytesMut::new();
+        for message in &messages {
+            tokio_util::codec::Encoder::encode(&mut codec, message, &mut encoded).unwrap();
+        }
+
+        let mut src = bytes::BytesMut::new();
+        let mut received = Vec::with_capacity(messages.len());
+        for byte in encoded {
+            src.extend_from_slice(&[byte]);
+            if let Some(message) = codec.decode(&mut src).unwrap() {
+                assert!(src.is_empty());
+                received.push(message);
+            }
+        }
+
+        assert_eq!(received, messages);
+
+        // a value which exceeds the decoder's limits is rejected before it's complete
+        let decoder_options = DecoderOptions::new().max_bytes(16);
+        let mut codec =
+            TbonCodec::<String>::with_options((), EncoderOptions::new(), decoder_options);
+        let encoded = super::to_vec("a string which is longer than 16 bytes").unwrap();
+        let mut src = bytes::BytesMut::from(&encoded[..20]);
+        let error = codec.decode(&mut src).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::LimitExceeded);
+    }
+
+    #[tokio::test]
+    async fn test_zero_copy() {
+        let blob = Bytes::from((0..=255u8).cycle().take(10_000).collect::<Vec<u8>>());
+        let value = (
+            "plain".to_string(),
+            "escaped \\ \"string\"".to_string(),
+            blob.clone(),
+            Bytes::from_static(b"plain"),
+            vec![1u64, 2, 3],
+        );
+
+        let encoded = Bytes::from(super::to_vec(&value).unwrap());
+        type Value = (String, String, Bytes, Bytes, Vec<u64>);
+
+        // decode from a single chunk, and from chunks which split every value
+        for chunk_size in [encoded.len(), 3] {
+            let chunks = encoded
+                .chunks(chunk_size)
+                .map(|chunk| encoded.slice_ref(chunk))
+                .collect::<Vec<Bytes>>();
+
+            let decoded: Value = decode((), futures::stream::iter(chunks)).await.unwrap();
+            assert_eq!(decoded, value);
+        }
+
+        let encoded = Bytes::from(super::to_vec(Bytes::from_static(b"plain")).unwrap());
+        let source = futures::stream::iter(vec![Ok(encoded.clone())]);
+        let mut decoder = Decoder::from_stream(source);
+        let decoded = decoder.decode_byte_buffer().await.unwrap();
+        assert_eq!(decoded, b"plain"[..]);
+
+        let range = encoded.as_ptr_range();
+        assert!(range.contains(&decoded.as_ptr()));
+
+        let encoded = super::to_vec(Bytes::from_static(b"a=b")).unwrap();
+        let source = futures::stream::iter(vec![Ok(Bytes::from(encoded))]);
+        let mut decoder = Decoder::from_stream(source);
+        let decoded = decoder.decode_byte_buffer().await.unwrap();
+        assert_eq!(decoded, b"a=b"[..]);
+
+        // a FromStream type which calls decode_bytes reads the same bytes from the buffer
+        // as it would from an escaped array, or an array split across chunks
+        #[derive(Debug)]
+        struct Blob(Vec<u8>);
+
+        struct BlobVisitor;
+
+        impl destream::de::Visitor for BlobVisitor {
+            type Value = Blob;
+
+            fn expecting() -> &'static str {
+                "a blob"
+            }
+
+            async fn visit_array_u8<A: destream::de::ArrayAccess<u8>>(
+                self,
+                mut access: A,
+            ) -> Result<Blob, A::Error> {
+                let mut blob = Vec::new();
+                let mut buffer = [0u8; 100];
+
+                loop {
+                    let len = access.buffer(&mut buffer).await?;
+                    if len == 0 {
+                        break Ok(Blob(blob));
+                    }
+
+                    blob.extend_from_slice(&buffer[..len]);
+                }
+            }
+        }
+
+        impl FromStream for Blob {
+            type Context = ();
+
+            async fn from_stream<D: destream::de::Decoder>(
+                _: (),
+                decoder: &mut D,
+            ) -> Result<Self, D::Error> {
+                decoder.decode_bytes(BlobVisitor).await
+            }
+        }
+
+        for blob in [
+            blob.slice(..1_000),
+            Bytes::from_static(b"a=b\\c"),
+            Bytes::new(),
+        ] {
+            let encoded = Bytes::from(super::to_vec((blob.clone(), 1u8)).unwrap());
+
+            let (decoded, after): (Blob, u8) = super::from_bytes((), encoded.clone()).unwrap();
+            assert_eq!(decoded.0, blob);
+            assert_eq!(after, 1);
+
+            let chunks = encoded.chunks(7).map(Bytes::copy_from_slice);
+            let (decoded, _): (Blob, u8) = decode((), futures::stream::iter(chunks)).await.unwrap();
+            assert_eq!(decoded.0, blob);
+        }
+
+        let encoded = super::to_vec(blob.slice(..1_000)).unwrap();
+        let options = DecoderOptions::new().max_array_len(999);
+        let error = from_slice_with_options::<Blob>((), &encoded, options).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::LimitExceeded);
+    }
+
+    #[tokio::test]
+    async fn test_length_prefixed_arrays() {
+        use super::{Array, Value};
+
+        let options = EncoderOptions::new().length_prefixed_arrays(true);
+
+        let mut rng = rand::rng();
+        let blob: Vec<u8> = (0..1000).map(|_| rng.random()).collect();
+        let floats: Vec<f32> = (0..100).map(|_| rng.random()).collect();
+
+        let mut map = BTreeMap::new();
+        map.insert("floats".to_string(), floats.clone());
+        map.insert("empty".to_string(), vec![]);
+
+        let value = (
+            Bytes::from(blob.clone()),
+            vec![vec![-1i64, 93], vec![]],
+            map,
+        );
+        let encoded = Bytes::from(to_vec_with_options(&value, options).unwrap());
+        type Value3 = (Bytes, Vec<Vec<i64>>, BTreeMap<String, Vec<f32>>);
+
+        // decode from a single chunk, and from chunks which split every value
+        for chunk_size in [encoded.len(), 3] {
+            let chunks = encoded
+                .chunks(chunk_size)
+                .map(|chunk| encoded.slice_ref(chunk))
+                .collect::<Vec<Bytes>>();
+
+            let decoded: Value3 = decode((), futures::stream::iter(chunks)).await.unwrap();
+            assert_eq!(decoded, value);
+        }
+
+        // random bytes are not escaped, so the encoded size is predictable
+        let encoded = to_vec_with_options(Bytes::from(blob.clone()), options).unwrap();
+        assert_eq!(encoded.len(), 2 + 2 + blob.len() + 1);
+        assert_eq!(&encoded[..2], b"#\x09");
+
+        let source = futures::stream::iter(vec![Ok(Bytes::from(encoded))]);
+        let mut decoder = Decoder::from_stream(source);
+        assert_eq!(decoder.decode_byte_buffer().await.unwrap(), blob);
+
+        let stats = super::debug::stats(&to_vec_with_options(&value, options).unwrap());
+        assert_eq!(stats.unwrap().escapes, 0);
+
+        // an array of unknown length is encoded one chunk at a time
+        let chunks = futures::stream::iter(vec![vec![1u16, 2], vec![], vec![0x3d5c]]);
+        let encoded: Vec<Bytes> =
+            destream::en::Encoder::encode_array_u16(Encoder::with_options(options), chunks)
+                .unwrap()
+                .try_collect()
+                .await
+                .unwrap();
+
+        let encoded = encoded.concat();
+        assert_eq!(encoded, b"#\x0a\x02\x00\x01\x00\x02\x01\x3d\x5c\x00");
+        assert_eq!(
+            super::debug::dump(&encoded).trim(),
+            "0  array of 16-bit unsigned int (3 elements) [1, 2, 15708]"
+        );
+
+        let expected = Value::Array(Array::U16(vec![1, 2, 0x3d5c]));
+        assert_eq!(from_slice::<Value>((), &encoded).unwrap(), expected);
+
+        // an empty buffer reads no elements, even when the next element spans two source chunks
+        struct Halves(Vec<u16>);
+
+        struct HalvesVisitor;
+
+        impl destream::de::Visitor for HalvesVisitor {
+            type Value = Halves;
+
+            fn expecting() -> &'static str {
+                "an array of u16"
+            }
+
+            async fn visit_array_u16<A: destream::de::ArrayAccess<u16>>(
+                self,
+                mut access: A,
+            ) -> Result<Halves, A::Error> {
+                let mut array = Vec::new();
+                let mut buffer = [0u16; 2];
+
+                loop {
+                    assert_eq!(access.buffer(&mut []).await?, 0);
+
+                    let len = access.buffer(&mut buffer).await?;
+                    if len == 0 {
+                        break Ok(Halves(array));
+                    }
+
+                    array.extend_from_slice(&buffer[..len]);
+                }
+            }
+        }
+
+        impl FromStream for Halves {
+            type Context = ();
+
+            async fn from_stream<D: destream::de::Decoder>(
+                _: (),
+                decoder: &mut D,
+            ) -> Result<Self, D::Error> {
+                decoder.decode_array_u16(HalvesVisitor).await
+            }
+        }
+
+        let chunks = encoded.iter().map(|byte| Bytes::copy_from_slice(&[*byte]));
+        let decoded: Halves = decode((), futures::stream::iter(chunks)).await.unwrap();
+        assert_eq!(decoded.0, [1, 2, 0x3d5c]);
+
+        let encoded = to_vec_with_options(&floats, options).unwrap();
+        let decode_options = DecoderOptions::new().max_array_len(99);
+        let result: Result<Vec<f32>, _> = from_slice_with_options((), &encoded, decode_options);
+        assert_eq!(result.unwrap_err().kind(), ErrorKind::LimitExceeded);
+
+        // a length-prefixed array can be skipped over as part of a larger value
+        let encoded = to_vec_with_options((&floats, "after"), options).unwrap();
+        let (_, after): (destream::IgnoredAny, String) = from_slice((), &encoded).unwrap();
+        assert_eq!(after, "after");
+
+        let truncated = &encoded[..50];
+        let result: Result<(Vec<f32>, String), _> = from_slice((), truncated);
+        assert_eq!(result.unwrap_err().kind(), ErrorKind::UnexpectedEnd);
+    }
+
+    #[tokio::test]
+    async fn test_compact_integers() {
+        use super::{Array, Value};
+
+        let options = EncoderOptions::new().compact_integers(true);
+
+        assert_eq!(to_vec_with_options(3u64, options).unwrap(), [19, 3]);
+        assert_eq!(to_vec_with_options(-1i64, options).unwrap(), [18, 1]);
+        assert_eq!(
+            to_vec_with_options(300u32, options).unwrap(),
+            [19, 0xac, 0x02]
+        );
+
+        // a value which is not shorter as a varint keeps its fixed-width encoding
+        assert_eq!(to_vec_with_options(7u8, options).unwrap(), [9, 7]);
+        assert_eq!(to_vec_with_options(u64::MAX, options).unwrap().len(), 9);
+
+        let value = (
+            (0u16, i16::MIN, u32::MAX, i32::MIN + 1),
+            (0x3d5c5d7d_u64, i64::MIN, -64i64, 64u64),
+            vec![1u64, 2, 3],
+            Some(1_000_000u32),
+        );
+
+        let encoded = Bytes::from(to_vec_with_options(&value, options).unwrap());
+        assert!(encoded.len() < super::to_vec(&value).unwrap().len());
+        type Value4 = (
+            (u16, i16, u32, i32),
+            (u64, i64, i64, u64),
+            Vec<u64>,
+            Option<u32>,
+        );
+
+        // decode from a single chunk, and from chunks which split every value
+        for chunk_size in [encoded.len(), 1] {
+            let chunks = encoded
+                .chunks(chunk_size)
+                .map(|chunk| encoded.slice_ref(chunk))
+                .collect::<Vec<Bytes>>();
+
+            let decoded: Value4 = decode((), futures::stream::iter(chunks)).await.unwrap();
+            assert_eq!(decoded, value);
+        }
+
+        assert!(super::debug::validate(&encoded).is_ok());
+        let (_, after): (destream::IgnoredAny, String) = from_slice(
+            (),
+            &to_vec_with_options((&value, "after"), options).unwrap(),
+        )
+        .unwrap();
+
+        assert_eq!(after, "after");
+
+        // a varint decodes as any integer type which can hold its value
+        let encoded = to_vec_with_options(300u64, options).unwrap();
+        assert_eq!(from_slice::<i16>((), &encoded).unwrap(), 300);
+        assert!(from_slice::<u8>((), &encoded).is_err());
+        assert!(from_slice::<f64>((), &encoded).is_err());
+        assert_eq!(from_slice::<Value>((), &encoded).unwrap(), Value::U64(300));
+        assert_eq!(
+            super::debug::dump(&encoded).trim(),
+            "0  variable-length unsigned int 300"
+        );
+
+        let encoded = to_vec_with_options(-300i32, options).unwrap();
+        assert_eq!(from_slice::<i64>((), &encoded).unwrap(), -300);
+        assert!(from_slice::<u64>((), &encoded).is_err());
+        assert!(from_slice::<i8>((), &encoded).is_err());
+        assert_eq!(from_slice::<Value>((), &encoded).unwrap(), Value::I64(-300));
+
+        let encoder = Encoder::with_options(options);
+        let chunks: Vec<Bytes> = encoder
+            .encode_i128(i128::MIN + 1)
+            .unwrap()
+            .try_collect()
+            .await
+            .unwrap();
+        let encoded = chunks.concat();
+        assert_eq!(encoded.len(), 17);
+
+        let chunks: Vec<Bytes> = encoder
+            .encode_u128(1 << 100)
+            .unwrap()
+            .try_collect()
+            .await
+            .unwrap();
+        let encoded = chunks.concat();
+        assert_eq!(encoded.len(), 16);
+        let source = futures::stream::iter([Ok::<_, super::de::Error>(Bytes::from(encoded))]);
+        assert_eq!(
+            Decoder::from_stream(source).decode_u128().await.unwrap(),
+            1 << 100
+        );
+
+        // arrays keep their fixed-width encoding
+        let array = Value::Array(Array::U64(vec![1, 2, 3]));
+        assert_eq!(
+            to_vec_with_options(&array, options).unwrap(),
+            super::to_vec(&array).unwrap()
+        );
+
+        // a Value doesn't record that an integer was a varint, but it decodes one encoded from a
+        // 128-bit integer as a 128-bit integer, so it re-encodes to the same compact bytes
+        let value = Value::List(vec![
+            Value::I32(-300),
+            Value::U8(7),
+            Value::U64(1 << 60),
+            Value::U128(1 << 60),
+            Value::I128(-(1 << 100)),
+        ]);
+        let encoded = to_vec_with_options(&value, options).unwrap();
+        let decoded = from_slice::<Value>((), &encoded).unwrap();
+        assert_eq!(
+            decoded,
+            Value::List(vec![
+                Value::I64(-300),
+                Value::U8(7),
+                Value::U64(1 << 60),
+                Value::U128(1 << 60),
+                Value::I128(-(1 << 100)),
+            ])
+        );
+        assert_eq!(to_vec_with_options(&decoded, options).unwrap(), encoded);
+
+        // but with the default options, each varint re-encodes as a fixed-width integer
+        let fixed = super::to_vec(&decoded).unwrap();
+        assert_eq!(fixed.len(), encoded.len() + (9 - 3) + (17 - 10) + (17 - 16));
+
+        let mut overflow = vec![19];
+        overflow.extend([0xff; 19]);
+        overflow.push(0x01);
+        let error = from_slice::<u64>((), &overflow).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::InvalidLength);
+        assert!(super::debug::validate(&overflow).is_err());
+
+        let truncated = [19, 0x80];
+        let error = from_slice::<u64>((), &truncated).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::UnexpectedEnd);
+    }
+
+    #[tokio::test]
+    async fn test_encoder_options() {
+        // canonical maps are encoded in the same order regardless of insertion order
+        let options = EncoderOptions::new().canonical_maps(true);
+        let forward: HashMap<String, u32> = (0..100).map(|i| (i.to_string(), i)).collect();
+        let mut reverse: HashMap<String, u32> = HashMap::with_capacity(100);
+        for i in (0..100).rev() {
+            reverse.insert(i.to_string(), i);
+        }
+
+        let encoded = to_vec_with_options(&forward, options).unwrap();
+        assert_eq!(encoded, to_vec_with_options(&reverse, options).unwrap());
+        let decoded: HashMap<String, u32> = from_slice((), &encoded).unwrap();
+        assert_eq!(decoded, forward);
+
+        let entries = vec![("b", 1u8), ("a", 2u8)];
+        let encoded: Vec<Bytes> = encode_map_with_options(futures::stream::iter(entries), options)
+            .try_collect()
+            .await
+            .unwrap();
+
+        let sorted = vec![("a", 2u8), ("b", 1u8)];
+        let expected: Vec<Bytes> = encode_map(futures::stream::iter(sorted))
+            .try_collect()
+            .await
+            .unwrap();
+
+        assert_eq!(encoded.concat(), expected.concat());
+
+        let duplicates = vec![("a", 1u8), ("a", 2u8)];
+        let result: Result<Vec<Bytes>, _> =
+            encode_map_with_options(futures::stream::iter(duplicates), options)
+                .try_collect()
+                .await;
+
+        assert!(result.is_err());
+
+        // the nesting depth of lists and maps is limited
+        let nested = vec![vec![vec![1u64]]];
+        let options = EncoderOptions::new().max_depth(3);
+        assert!(to_vec_with_options(&nested, options).is_ok());
+        let options = EncoderOptions::new().max_depth(2);
+        assert!(to_vec_with_options(&nested, options).is_err());
+
+        let seq = futures::stream::iter(vec![vec![1u64]]);
+        let result: Result<Vec<Bytes>, _> = encode_seq_with_options(seq, options.max_depth(1))
+            .try_collect()
+            .await;
+
+        assert!(result.is_err());
+
+        // a magic header is written before the value and checked before decoding it
+        let value = ("one".to_string(), 2u64);
+        let options = EncoderOptions::new().magic_header(b"TBON");
+        let encoded = to_vec_with_options(&value, options).unwrap();
+        assert_eq!(&encoded[..4], b"TBON");
+        assert_eq!(&encoded[4..], super::to_vec(&value).unwrap());
+
+        let decoder_options = DecoderOptions::new().magic_header(b"TBON");
+        let decoded: (String, u64) =
+            from_slice_with_options((), &encoded, decoder_options).unwrap();
+        assert_eq!(decoded, value);
+
+        let unmarked = super::to_vec(&value).unwrap();
+        let result: Result<(String, u64), _> =
+            from_slice_with_options((), &unmarked, decoder_options);
+
+        assert_eq!(result.unwrap_err().kind(), ErrorKind::MissingHeader);
+
+        let result: Result<(String, u64), _> = from_slice_with_options((), b"TB", decoder_options);
+        assert_eq!(result.unwrap_err().kind(), ErrorKind::MissingHeader);
+
+        // the offset of an error in the value itself counts the 4-byte header before it
+        let encoded = to_vec_with_options(Bytes::from_static(b"abc"), options).unwrap();
+        let result: Result<Uuid, _> = from_slice_with_options((), &encoded, decoder_options);
+        assert_eq!(result.unwrap_err().offset(), Some(4));
+
+        // adjacent chunks are merged into chunks of about the given size
+        let list: Vec<(String, u8)> = (0..1000).map(|i| (i.to_string(), i as u8)).collect();
+        let options = EncoderOptions::new().chunk_size(1024);
+        let chunks: Vec<Bytes> = encode_with_options(&list, options)
+            .unwrap()
+            .try_collect()
+            .await
+            .unwrap();
+
+        let (last, chunks) = chunks.split_last().unwrap();
+        assert!(chunks.iter().all(|chunk| chunk.len() >= 1024));
+
+        let mut encoded = chunks.concat();
+        encoded.extend_from_slice(last);
+        assert_eq!(encoded, super::to_vec(&list).unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_chunk_coalescing() {
+        use futures::channel::mpsc;
+        use futures::{FutureExt, StreamExt};
+
+        // small chunks are merged by default
+        let list: Vec<(String, u8)> = (0..10_000).map(|i| (i.to_string(), i as u8)).collect();
+        let chunks: Vec<Bytes> = encode(&list).unwrap().try_collect().await.unwrap();
+        let encoded = chunks.concat();
+        assert!(chunks.len() <= encoded.len() / 8192 + 1);
+
+        let options = EncoderOptions::new().chunk_size(0);
+        let uncoalesced: Vec<Bytes> = encode_with_options(&list, options)
+            .unwrap()
+            .try_collect()
+            .await
+            .unwrap();
+
+        assert!(uncoalesced.len() > list.len());
+        assert_eq!(uncoalesced.concat(), encoded);
+
+        // a streaming list is not held back waiting for its next element
+        let (sender, receiver) = mpsc::unbounded();
+        let mut encoded = encode_seq(receiver);
+
+        sender.unbounded_send("one".to_string()).unwrap();
+        let chunk = encoded.next().now_or_never().expect("chunk");
+        assert_eq!(chunk.unwrap().unwrap(), &b"[\"one\""[..]);
+        assert!(encoded.next().now_or_never().is_none());
+
+        sender.unbounded_send("two".to_string()).unwrap();
+        drop(sender);
+        let rest: Vec<Bytes> = encoded.try_collect().await.unwrap();
+        assert_eq!(rest.concat(), b"\"two\"]");
+
+        // and neither is a streaming map
+        let (sender, receiver) = mpsc::unbounded();
+        let mut encoded = encode_map(receiver);
+
+        sender.unbounded_send(("one".to_string(), true)).unwrap();
+        let chunk = encoded.next().now_or_never().expect("chunk");
+        assert_eq!(chunk.unwrap().unwrap().len(), 8);
+        assert!(encoded.next().now_or_never().is_none());
+
+        drop(sender);
+        let rest: Vec<Bytes> = encoded.try_collect().await.unwrap();
+        assert_eq!(rest.concat(), b"}");
+
+        // but a canonical map is held back until its source ends, since its entries are sorted
+        let (sender, receiver) = mpsc::unbounded();
+        let options = EncoderOptions::new().canonical_maps(true);
+        let mut encoded = encode_map_with_options(receiver, options);
+
+        sender.unbounded_send(("two".to_string(), 2u8)).unwrap();
+        sender.unbounded_send(("one".to_string(), 1)).unwrap();
+        assert!(encoded.next().now_or_never().is_none());
+
+        drop(sender);
+        let rest: Vec<Bytes> = encoded.try_collect().await.unwrap();
+        let map = BTreeMap::from_iter([("one".to_string(), 1u8), ("two".to_string(), 2)]);
+        assert_eq!(rest.concat(), to_vec_with_options(&map, options).unwrap());
+    }
+
+    #[test]
+    fn test_long_list() {
+        use futures::executor::block_on;
+        use futures::StreamExt;
+
+        // dropping an encoded list must not recurse once per element
+        let list = vec![1u8; 10_000_000];
+        let mut encoded = encode(list).unwrap();
+        let first = block_on(encoded.next()).unwrap().unwrap();
+        assert!(first.len() >= 8192);
+        drop(encoded);
+    }
+
     #[tokio::test]
     async fn test_uuid() {
+        use super::{Array, Value};
+
         run_test(Uuid::from_bytes([0u8; 16].into())).await;
+
+        // a UUID is encoded as its type bit followed by 16 unescaped bytes
+        let uuid = Uuid::from_bytes(*b"=\\[]{}\"=\\[]{}\"=\\");
+        let encoded = super::to_vec(uuid).unwrap();
+        assert_eq!(encoded.len(), 17);
+        assert_eq!(&encoded[1..], uuid.as_bytes());
+        assert_eq!(super::from_slice::<Uuid>((), &encoded).unwrap(), uuid);
+        assert_eq!(
+            super::debug::dump(&encoded).trim(),
+            format!("0  UUID {}", uuid)
+        );
+
+        // a Value keeps the UUID type bit, so that it re-encodes to the same bytes
+        let value = super::from_slice::<Value>((), &encoded).unwrap();
+        assert_eq!(value, Value::Uuid(uuid));
+        assert_eq!(super::to_vec(&value).unwrap(), encoded);
+        assert_eq!(super::to_vec(value).unwrap(), encoded);
+
+        let value = Value::List(vec![Value::Uuid(Uuid::nil()), Value::Uuid(uuid)]);
+        let encoded_list = super::to_vec(&value).unwrap();
+        assert_eq!(
+            super::from_slice::<Value>((), &encoded_list).unwrap(),
+            value
+        );
+        assert_eq!(
+            super::from_slice::<Vec<Uuid>>((), &encoded_list).unwrap(),
+            [Uuid::nil(), uuid]
+        );
+
+        let tuple = (uuid, Uuid::nil(), "uuid".to_string());
+        run_test(tuple).await;
+
+        // a UUID encoded as a u8 array prior to the UUID type bit can still be decoded
+        let legacy = super::to_vec(Value::Array(Array::U8(uuid.as_bytes().to_vec()))).unwrap();
+        assert_eq!(legacy.len(), 16 + 3 + 6);
+        assert_eq!(super::from_slice::<Uuid>((), &legacy).unwrap(), uuid);
+
+        let mut stream = b"[".to_vec();
+        stream.extend_from_slice(&encoded);
+        stream.extend_from_slice(&legacy);
+        stream.extend_from_slice(b"]");
+        assert_eq!(
+            super::from_slice::<(Uuid, Uuid)>((), &stream).unwrap(),
+            (uuid, uuid)
+        );
+        super::from_slice::<destream::IgnoredAny>((), &stream).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_128_bit() {
+        use super::{Array, Value};
+
+        async fn collect(stream: ByteStream<'static>) -> Vec<u8> {
+            let chunks: Vec<Bytes> = stream.try_collect().await.unwrap();
+            chunks.concat()
+        }
+
+        for u in [0, 1, u64::MAX as u128 + 1, 0x3d5c_5b5d << 64, u128::MAX] {
+            let encoded = collect(Encoder::new().encode_u128(u).unwrap()).await;
+            assert_eq!(encoded.len(), 17);
+
+            let mut decoder =
+                Decoder::from_stream(futures::stream::iter([Ok::<_, super::de::Error>(
+                    Bytes::from(encoded.clone()),
+                )]));
+
+            assert_eq!(decoder.decode_u128().await.unwrap(), u);
+            assert_eq!(
+                super::debug::dump(&encoded).trim(),
+                format!("0  128-bit unsigned int {}", u)
+            );
+        }
+
+        for i in [i128::MIN, -1, 0, i128::MAX] {
+            let encoded = collect(Encoder::new().encode_i128(i).unwrap()).await;
+            let mut decoder =
+                Decoder::from_stream(futures::stream::iter([Ok::<_, super::de::Error>(
+                    Bytes::from(encoded),
+                )]));
+
+            assert_eq!(decoder.decode_i128().await.unwrap(), i);
+        }
+
+        // a 128-bit integer which fits in 64 bits decodes as a 64-bit integer
+        let encoded = collect(Encoder::new().encode_i128(-5).unwrap()).await;
+        assert_eq!(from_slice::<i64>((), &encoded).unwrap(), -5);
+        assert!(from_slice::<u64>((), &encoded).is_err());
+
+        // but a Value keeps its width, so that it re-encodes to the same bytes
+        let value = from_slice::<Value>((), &encoded).unwrap();
+        assert_eq!(value, Value::I128(-5));
+        assert_eq!(super::to_vec(&value).unwrap(), encoded);
+
+        let encoded = collect(Encoder::new().encode_u128(u128::MAX).unwrap()).await;
+        assert!(from_slice::<u64>((), &encoded).is_err());
+        let value = from_slice::<Value>((), &encoded).unwrap();
+        assert_eq!(value, Value::U128(u128::MAX));
+        assert_eq!(super::to_vec(&value).unwrap(), encoded);
+        assert_eq!(super::to_vec(value).unwrap(), encoded);
+
+        let array = vec![0x3d5c, u64::MAX as u128];
+        let encoded = collect(
+            Encoder::new()
+                .encode_array_u128(futures::stream::once(future::ready(array.clone())))
+                .unwrap(),
+        )
+        .await;
+        let value = from_slice::<Value>((), &encoded).unwrap();
+        assert_eq!(value, Value::Array(Array::U128(array)));
+        assert_eq!(super::to_vec(&value).unwrap(), encoded);
+        assert!(super::debug::validate(&encoded).is_ok());
+
+        let array = vec![-1i128, i128::MAX, i128::MIN];
+        for options in [
+            EncoderOptions::default(),
+            EncoderOptions::default().length_prefixed_arrays(true),
+        ] {
+            let encoded = collect(
+                Encoder::with_options(options)
+                    .encode_array_i128(futures::stream::once(future::ready(array.clone())))
+                    .unwrap(),
+            )
+            .await;
+
+            let value = from_slice::<Value>((), &encoded).unwrap();
+            assert_eq!(value, Value::Array(Array::I128(array.clone())));
+            assert_eq!(
+                super::en::to_vec_with_options(&value, options).unwrap(),
+                encoded
+            );
+        }
+
+        // a 128-bit Value nested in a list or map re-encodes to the same bytes
+        let value = Value::List(vec![
+            Value::I128(i128::MIN),
+            Value::Map(vec![(Value::U128(1), Array::I128(vec![]).into())]),
+        ]);
+        let encoded = super::to_vec(&value).unwrap();
+        assert_eq!(from_slice::<Value>((), &encoded).unwrap(), value);
+        assert!(from_slice::<(i64, Value)>((), &encoded).is_err());
+
+        // any other visitor is visited with a 64-bit integer, whatever it expects
+        struct Wide(i64);
+
+        struct WideVisitor;
+
+        impl destream::de::Visitor for WideVisitor {
+            type Value = Wide;
+
+            fn expecting() -> &'static str {
+                "a TBON value"
+            }
+
+            fn visit_i64<E: destream::de::Error>(self, i: i64) -> Result<Wide, E> {
+                Ok(Wide(i))
+            }
+        }
+
+        impl FromStream for Wide {
+            type Context = ();
+
+            async fn from_stream<D: destream::de::Decoder>(
+                _: (),
+                decoder: &mut D,
+            ) -> Result<Self, D::Error> {
+                decoder.decode_any(WideVisitor).await
+            }
+        }
+
+        let encoded = collect(Encoder::new().encode_i128(-5).unwrap()).await;
+        assert_eq!(from_slice::<Wide>((), &encoded).unwrap().0, -5);
+
+        // a 128-bit integer can be skipped over as part of a larger value
+        let mut encoded = b"[".to_vec();
+        encoded.extend(collect(Encoder::new().encode_i128(i128::MIN).unwrap()).await);
+        encoded.extend(super::to_vec(1u8).unwrap());
+        encoded.extend(b"]");
+        from_slice::<destream::IgnoredAny>((), &encoded).unwrap();
+    }
+
+    #[cfg(feature = "half")]
+    #[tokio::test]
+    async fn test_half() {
+        use destream::de::{ArrayAccess, Decoder as _};
+        use half::{bf16, f16};
+
+        use super::{Array, Value};
+
+        fn decoder_for(encoded: Vec<u8>) -> Decoder<impl Read> {
+            // split the source into single bytes to exercise buffering
+            let chunks = encoded
+                .into_iter()
+                .map(|byte| Ok::<_, super::de::Error>(Bytes::copy_from_slice(&[byte])));
+
+            Decoder::from_stream(futures::stream::iter(chunks.collect::<Vec<_>>()))
+        }
+
+        async fn collect(stream: ByteStream<'static>) -> Vec<u8> {
+            let chunks: Vec<Bytes> = stream.try_collect().await.unwrap();
+            chunks.concat()
+        }
+
+        // includes an element whose encoding consists of a delimiter byte and an escape byte
+        let halves = vec![
+            f16::from_f32(1.5),
+            f16::MIN,
+            f16::from_bits(0x3d5c),
+            f16::NAN,
+        ];
+        let chunks = futures::stream::once(future::ready(halves.clone()));
+        let encoded = collect(Encoder::new().encode_array_f16(chunks).unwrap()).await;
+        assert_eq!(encoded.len(), 3 + (2 * halves.len()) + 2);
+
+        let mut decoder = decoder_for(encoded.clone());
+        let mut array = decoder.decode_array_f16().await.unwrap();
+        let mut buffer = [f16::ZERO; 3];
+        let mut actual = Vec::new();
+        loop {
+            let len = array.buffer(&mut buffer).await.unwrap();
+            if len == 0 {
+                break;
+            }
+
+            actual.extend_from_slice(&buffer[..len]);
+        }
+
+        assert_eq!(actual[..3], halves[..3]);
+        assert!(actual[3].is_nan());
+
+        // a Value keeps the width of each element, so that it re-encodes to the same bytes
+        let value = from_slice::<Value>((), &encoded).unwrap();
+        match &value {
+            Value::Array(Array::F16(actual)) => {
+                assert_eq!(actual[..3], halves[..3]);
+                assert!(actual[3].is_nan());
+            }
+            other => panic!("expected an f16 array but found {}", other),
+        }
+        assert_eq!(super::to_vec(&value).unwrap(), encoded);
+
+        let brains = vec![bf16::from_f32(-2.), bf16::MAX];
+        let chunks = futures::stream::once(future::ready(brains.clone()));
+        let encoded = collect(Encoder::new().encode_array_bf16(chunks).unwrap()).await;
+        let mut decoder = decoder_for(encoded.clone());
+        let mut array = decoder.decode_array_bf16().await.unwrap();
+        let mut buffer = [bf16::ZERO; 4];
+        assert_eq!(array.buffer(&mut buffer).await.unwrap(), 2);
+        assert_eq!(buffer[..2], brains);
+
+        let expected = Value::Array(Array::BF16(brains));
+        assert_eq!(from_slice::<Value>((), &encoded).unwrap(), expected);
+        assert_eq!(super::to_vec(expected).unwrap(), encoded);
+
+        let options = EncoderOptions::default().length_prefixed_arrays(true);
+        let expected = Value::Array(Array::F16(vec![f16::from_bits(0x3d5c); 3]));
+        let encoded = super::en::to_vec_with_options(&expected, options).unwrap();
+        assert_eq!(encoded.len(), 2 + 1 + 6 + 1);
+        assert_eq!(from_slice::<Value>((), &encoded).unwrap(), expected);
+
+        let encoded = collect(Encoder::new().encode_f16(f16::from_f32(0.25)).unwrap()).await;
+        assert_eq!(
+            decoder_for(encoded.clone()).decode_f16().await.unwrap(),
+            f16::from_f32(0.25)
+        );
+        assert_eq!(from_slice::<f32>((), &encoded).unwrap(), 0.25);
+        let value = from_slice::<Value>((), &encoded).unwrap();
+        assert_eq!(value, Value::F16(f16::from_f32(0.25)));
+        assert_eq!(super::to_vec(&value).unwrap(), encoded);
+        assert_eq!(super::debug::dump(&encoded).trim(), "0  16-bit float 0.25");
+
+        let scalar = collect(Encoder::new().encode_bf16(bf16::from_f32(-4.)).unwrap()).await;
+        let encoded = scalar.clone();
+        assert_eq!(
+            decoder_for(encoded.clone()).decode_bf16().await.unwrap(),
+            bf16::from_f32(-4.)
+        );
+        assert_eq!(from_slice::<f32>((), &encoded).unwrap(), -4.);
+        assert!(from_slice::<f64>((), &encoded).is_err());
+        let value = from_slice::<Value>((), &encoded).unwrap();
+        assert_eq!(value, Value::BF16(bf16::from_f32(-4.)));
+        assert_eq!(super::to_vec(value).unwrap(), encoded);
+
+        // a 16-bit float can be skipped over as part of a larger value
+        let mut encoded = b"[".to_vec();
+        encoded.extend(scalar);
+        encoded.extend(b"]");
+        let mut decoder = decoder_for(encoded);
+        destream::IgnoredAny::from_stream((), &mut decoder)
+            .await
+            .unwrap();
+        assert!(decoder.decode_any(destream::IgnoredAny).await.is_err());
+    }
+
+    #[test]
+    fn test_value() {
+        let mut map = HashMap::new();
+        map.insert("one".to_string(), vec![1u64, 2, 3]);
+        let expected = (
+            "hello \"world\"".to_string(),
+            -2i16,
+            (),
+            vec![true, false],
+            Bytes::from_static(b"a=b\\c"),
+            map,
+            vec![Some(1.5f32), None],
+        );
+
+        let encoded = super::to_vec(&expected).unwrap();
+        let value: super::Value = super::from_slice((), &encoded).unwrap();
+        assert_eq!(super::to_vec(&value).unwrap(), encoded);
+        assert_eq!(super::to_vec(value.clone()).unwrap(), encoded);
+
+        assert_eq!(value[0].as_str(), Some("hello \"world\""));
+        assert_eq!(value[1], super::Value::I16(-2));
+        assert!(value[2].is_none());
+        assert_eq!(value[4].as_array().map(|a| a.len()), Some(5));
+        assert_eq!(value[5]["one"][2].as_u64(), Some(3));
+        assert!(value[5]["two"].is_none());
+        assert!(value[7].is_none());
+
+        assert_eq!(
+            value.to_string(),
+            r#"["hello \"world\"", -2i16, None, [true, false], u8[97, 61, 98, 92, 99], {"one": [1u64, 2u64, 3u64]}, [1.5f32, None]]"#
+        );
+
+        let arrays = super::Value::List(vec![
+            super::Array::Bool(vec![true]).into(),
+            super::Array::F32(vec![1.]).into(),
+            super::Array::F64(vec![-1., 2.]).into(),
+            super::Array::I16(vec![-1, 2]).into(),
+            super::Array::I32(vec![]).into(),
+            super::Array::I64(vec![i64::MIN]).into(),
+            super::Array::U8(vec![b'=', b'\\']).into(),
+            super::Array::U16(vec![u16::MAX]).into(),
+            super::Array::U32(vec![1, 2, 3]).into(),
+            super::Array::U64(vec![0]).into(),
+        ]);
+
+        let encoded = super::to_vec(&arrays).unwrap();
+        let decoded: super::Value = super::from_slice((), &encoded).unwrap();
+        assert_eq!(decoded, arrays);
+        assert_eq!(super::to_vec(&decoded).unwrap(), encoded);
+    }
+
+    #[tokio::test]
+    async fn test_array_dtypes() {
+        use super::{Array, Value};
+
+        // each array includes elements whose encoding contains a delimiter or escape byte
+        let arrays = vec![
+            Array::Bool(vec![true, false, true]),
+            Array::F32(vec![1.5, f32::from_bits(0x3d5c5b5d), -0.]),
+            Array::F64(vec![f64::MIN, f64::from_bits(0x3d5c5b5d5c3d5c5b)]),
+            Array::I8(vec![b'=' as i8, b'\\' as i8, i8::MIN, -1, 0]),
+            Array::I16(vec![0x3d5c, i16::MIN, -1]),
+            Array::I32(vec![0x3d5c5b5d, i32::MAX]),
+            Array::I64(vec![0x3d5c5b5d5c3d5c5b, i64::MIN]),
+            Array::I128(vec![0x3d5c5b5d5c3d5c5b << 64, i128::MIN]),
+            Array::U8(vec![b'=', b'\\', b'[', b']', b'{', b'}', b'"', u8::MAX]),
+            Array::U16(vec![0x5c3d, u16::MAX]),
+            Array::U32(vec![0x5c3d5d5b, 0]),
+            Array::U64(vec![0x5c3d5d5b3d5c5b5d, u64::MAX]),
+            Array::U128(vec![0x5c3d5d5b3d5c5b5d, u128::MAX]),
+        ];
+
+        for array in arrays {
+            let dtype = array.dtype();
+            let empty = match &array {
+                Array::Bool(_) => Array::Bool(vec![]),
+                #[cfg(feature = "half")]
+                Array::F16(_) => Array::F16(vec![]),
+                #[cfg(feature = "half")]
+                Array::BF16(_) => Array::BF16(vec![]),
+                Array::F32(_) => Array::F32(vec![]),
+                Array::F64(_) => Array::F64(vec![]),
+                Array::I8(_) => Array::I8(vec![]),
+                Array::I16(_) => Array::I16(vec![]),
+                Array::I32(_) => Array::I32(vec![]),
+                Array::I64(_) => Array::I64(vec![]),
+                Array::I128(_) => Array::I128(vec![]),
+                Array::U8(_) => Array::U8(vec![]),
+                Array::U16(_) => Array::U16(vec![]),
+                Array::U32(_) => Array::U32(vec![]),
+                Array::U64(_) => Array::U64(vec![]),
+                Array::U128(_) => Array::U128(vec![]),
+            };
+
+            for array in [array, empty] {
+                let expected = Value::Array(array);
+                let encoded = super::to_vec(&expected).unwrap();
+
+                let actual: Value = super::from_slice((), &encoded)
+                    .unwrap_or_else(|cause| panic!("{} array: {}", dtype, cause));
+
+                assert_eq!(actual, expected, "{} array", dtype);
+                assert_eq!(super::to_vec(&actual).unwrap(), encoded, "{} array", dtype);
+
+                let chunks = encoded
+                    .iter()
+                    .map(|byte| Bytes::copy_from_slice(&[*byte]))
+                    .collect::<Vec<_>>();
+
+                let actual: Value = decode((), futures::stream::iter(chunks)).await.unwrap();
+                assert_eq!(actual, expected, "{} array split into single bytes", dtype);
+            }
+        }
+    }
+
+    #[test]
+    fn test_debug_dump() {
+        let mut map = BTreeMap::new();
+        map.insert("a\"b".to_string(), (None::<u8>, vec![true]));
+        let encoded = super::to_vec(("hi", -1i16, map, Bytes::from_static(b"x=y"))).unwrap();
+
+        let expected = r#"       0  list
+       1    string "hi"
+       5    16-bit int -1
+       8    map
+       9      key: string "a\"b"
+      15      value: list
+      16        none
+      17        list
+      18          boolean true
+      20        end list (1 items)
+      21      end list (2 items)
+      22    end map (1 entries)
+      23    array of 8-bit unsigned int (3 elements) [120, 61, 121]
+      30  end list (4 items)
+"#;
+
+        assert_eq!(super::debug::dump(&encoded), expected);
+
+        let truncated = super::debug::dump(&encoded[..20]);
+        assert!(truncated.starts_with(&expected[..expected.find("      20").unwrap()]));
+        assert!(truncated.ends_with(
+            "      20          !! unexpected end of input in list at 17 (parsing stopped here)\n"
+        ));
+
+        let invalid = super::debug::dump(b"[\x2a]");
+        assert!(invalid.ends_with("1    !! unknown type bit 0x2a (parsing stopped here)\n"));
+
+        let invalid = super::debug::dump(b"{]");
+        assert!(invalid.ends_with("1    !! unexpected delimiter ']' (parsing stopped here)\n"));
+    }
+
+    #[test]
+    fn test_debug_validate() {
+        let value = (
+            vec!["a\"b".to_string()],
+            vec![Bytes::from_static(b"=\\")],
+            1u16,
+        );
+        let encoded = super::to_vec(&value).unwrap();
+        assert_eq!(super::debug::validate(&encoded), Ok(()));
+
+        let stats = super::debug::stats(&encoded).unwrap();
+        assert_eq!(stats.bytes, encoded.len());
+        assert_eq!(stats.lists, 3);
+        assert_eq!(stats.max_depth, 2);
+        assert_eq!(stats.strings, 1);
+        assert_eq!(stats.escapes, 3);
+        assert_eq!(stats.scalars.get("16-bit unsigned int"), Some(&1));
+        assert_eq!(stats.array_elements.get("8-bit unsigned int"), Some(&2));
+
+        let invalid = super::debug::validate(&encoded[..encoded.len() - 4]).unwrap_err();
+        assert_eq!(invalid.offset(), encoded.len() - 4);
+
+        let invalid = super::debug::validate(b"[\"\xff\"\x2a]").unwrap_err();
+        assert_eq!(invalid.offset(), 1);
+    }
+
+    #[cfg(feature = "json")]
+    #[tokio::test]
+    async fn test_json() {
+        use super::{Array, Value};
+
+        async fn transcode<E: std::fmt::Display, S>(stream: S) -> Vec<u8>
+        where
+            S: futures::Stream<Item = Result<Bytes, E>>,
+        {
+            let chunks: Vec<Bytes> = stream
+                .map_err(|e| e.to_string())
+                .try_collect()
+                .await
+                .unwrap();
+            chunks.concat()
+        }
+
+        fn chunked(encoded: &[u8], size: usize) -> Vec<Result<Bytes, std::io::Error>> {
+            encoded
+                .chunks(size)
+                .map(|chunk| Ok(Bytes::copy_from_slice(chunk)))
+                .collect()
+        }
+
+        let value = Value::List(vec![
+            Value::None,
+            Value::Bool(true),
+            Value::F32(0.1),
+            Value::F64(1.0),
+            Value::F64(f64::NEG_INFINITY),
+            Value::I8(-8),
+            Value::I16(16),
+            Value::I32(-32),
+            Value::I64(i64::MIN),
+            Value::U8(8),
+            Value::U16(16),
+            Value::U32(32),
+            Value::U64(u64::MAX),
+            Value::String("\"quoted\"\n\\ \u{1}".into()),
+            Array::Bool(vec![]).into(),
+            Array::F32(vec![f32::NAN, 1.5]).into(),
+            Array::I8(vec![b'=' as i8, -1]).into(),
+            Array::U8((0..=255).collect()).into(),
+            Array::U64((0..10_000).collect()).into(),
+            Value::I128(i128::MIN),
+            Value::U128(7),
+            Value::Uuid(Uuid::from_u128(0x67e5504410b1426f9247bb680e5fe0c8)),
+            Array::I128(vec![-1, i128::MAX]).into(),
+            Array::U128(vec![u128::MAX, 0]).into(),
+            Value::List(vec![]),
+            Value::Map(vec![]),
+            Value::Map(vec![
+                ("/path".into(), Value::U8(1)),
+                ("//x".into(), Value::List(vec![Value::None])),
+                ("y".into(), Value::Map(vec![(Value::U8(1), "one".into())])),
+            ]),
+        ]);
+
+        let encoded = super::to_vec(&value).unwrap();
+
+        for size in [1, 7, encoded.len()] {
+            let source = futures::stream::iter(chunked(&encoded, size));
+            let json = transcode(super::json::to_json(source)).await;
+
+            let source = futures::stream::iter(chunked(&json, size));
+            let actual = transcode(super::json::from_json(source)).await;
+            assert!(
+                actual == encoded,
+                "round trip through {}",
+                String::from_utf8_lossy(&json)
+            );
+        }
+
+        let source = futures::stream::iter(chunked(&encoded, encoded.len()));
+        let json = transcode(super::json::to_json(source)).await;
+        let json = String::from_utf8(json).unwrap();
+        assert!(json.starts_with(
+            r#"[null,true,{"/f32":0.1},1.0,{"/f64":"-inf"},{"/i8":-8},{"/i16":16},{"/i32":-32},-9223372036854775808,"#
+        ));
+        assert!(json.ends_with(
+            r#"[],{},{"//path":{"/u8":1},"///x":[null],"y":{"/map":[[{"/u8":1},"one"]]}}]"#
+        ));
+        assert!(json.contains(
+            r#"{"/i128":"-170141183460469231731687303715884105728"},{"/u128":7},{"/uuid":"67e55044-10b1-426f-9247-bb680e5fe0c8"},{"/i128":[-1,"170141183460469231731687303715884105727"]},"#
+        ));
+
+        #[cfg(feature = "half")]
+        {
+            let value = Value::List(vec![
+                Value::F16(half::f16::from_f32(0.1)),
+                Value::BF16(half::bf16::NEG_INFINITY),
+                Array::F16(vec![half::f16::MAX, half::f16::from_bits(0x3d5c)]).into(),
+                Array::BF16(vec![half::bf16::from_f32(-2.5)]).into(),
+            ]);
+
+            let encoded = super::to_vec(&value).unwrap();
+            let source = futures::stream::iter(chunked(&encoded, 3));
+            let json = transcode(super::json::to_json(source)).await;
+            assert_eq!(
+                String::from_utf8_lossy(&json),
+                r#"[{"/f16":0.099975586},{"/bf16":"-inf"},{"/f16":[65504.0,1.3398438]},{"/bf16":[-2.5]}]"#
+            );
+
+            let source = futures::stream::iter(chunked(&json, 3));
+            assert_eq!(transcode(super::json::from_json(source)).await, encoded);
+        }
+
+        let json = br#"["\ud83d\ude00 \u0022\/\b"]"#;
+        let source = futures::stream::iter(chunked(json, 1));
+        let encoded = transcode(super::json::from_json(source)).await;
+        let decoded: Vec<String> = super::from_slice((), &encoded).unwrap();
+        assert_eq!(decoded, ["\u{1F600} \"/\u{8}"]);
+
+        let mixed = Value::Map(vec![("a".into(), Value::None), (Value::U8(1), Value::None)]);
+        let source = futures::stream::iter(chunked(&super::to_vec(&mixed).unwrap(), 64));
+        let chunks: Result<Vec<Bytes>, _> = super::json::to_json(source).try_collect().await;
+        assert!(chunks.is_err());
+
+        for invalid in [
+            r#"{"/u8": 256}"#,
+            r#"{"/u8": 1, "a": 2}"#,
+            r#"{"/x": 1}"#,
+            r#"{"/uuid": "x"}"#,
+            r#"{"/uuid": []}"#,
+            "[1,",
+        ] {
+            let source = futures::stream::iter(chunked(invalid.as_bytes(), 64));
+            let chunks: Result<Vec<Bytes>, _> = super::json::from_json(source).try_collect().await;
+            assert!(chunks.is_err(), "{}", invalid);
+        }
+    }
+
+    #[cfg(feature = "serde")]
+    #[tokio::test]
+    async fn test_serde() {
+        use serde_derive::{Deserialize, Serialize};
+
+        use super::de::ErrorKind;
+        use super::{Array, Value};
+
+        #[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+        enum Shape {
+            Empty,
+            Square(u32),
+            Line(i8, i8),
+            Rect { w: f32, h: f32 },
+        }
+
+        #[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+        struct Unit;
+
+        #[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+        struct Id(u64);
+
+        #[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+        struct Record {
+            id: Id,
+            name: String,
+            initial: char,
+            tags: Vec<String>,
+            shapes: Vec<Shape>,
+            weights: BTreeMap<String, f64>,
+            parent: Option<Box<Record>>,
+            unit: Unit,
+            #[serde(with = "serde_bytes")]
+            payload: Vec<u8>,
+        }
+
+        let child = Record {
+            id: Id(1),
+            name: "child \"quoted\"".into(),
+            initial: 'c',
+            tags: vec![],
+            shapes: vec![Shape::Empty],
+            weights: BTreeMap::new(),
+            parent: None,
+            unit: Unit,
+            payload: vec![],
+        };
+
+        let record = Record {
+            id: Id(u64::MAX),
+            name: "parent".into(),
+            initial: '\u{1F980}',
+            tags: vec!["a".into(), "b\\".into()],
+            shapes: vec![
+                Shape::Square(2),
+                Shape::Line(-1, 1),
+                Shape::Rect { w: 2., h: 3. },
+            ],
+            weights: BTreeMap::from_iter([("x".into(), 0.5), ("y".into(), -1.)]),
+            parent: Some(Box::new(child)),
+            unit: Unit,
+            payload: b"=[{\"}]=".to_vec(),
+        };
+
+        let encoded = crate::serde::to_vec(&record).unwrap();
+        assert_eq!(
+            crate::serde::from_slice::<Record>(&encoded).unwrap(),
+            record
+        );
+
+        let stream = crate::serde::encode(&record).unwrap();
+        let decoded: Record = crate::serde::try_decode(stream).await.unwrap();
+        assert_eq!(decoded, record);
+
+        // the representation documented in the serde module
+        let value: Value = from_slice((), &encoded).unwrap();
+        assert_eq!(value["id"], Value::U64(u64::MAX));
+        assert_eq!(value["initial"], Value::from("\u{1F980}"));
+        assert_eq!(value["payload"], Array::U8(b"=[{\"}]=".to_vec()).into());
+        assert_eq!(value["parent"]["parent"], Value::None);
+        assert_eq!(value["parent"]["unit"], Value::None);
+        assert_eq!(value["parent"]["shapes"][0], Value::from("Empty"));
+        assert_eq!(value["shapes"][0]["Square"], Value::U32(2));
+        assert_eq!(
+            value["shapes"][1]["Line"],
+            Value::List(vec![Value::I8(-1), Value::I8(1)])
+        );
+        assert_eq!(value["shapes"][2]["Rect"]["h"], Value::F32(3.));
+
+        // types encoded with destream can be decoded with serde, and vice versa
+        let expected = ("one".to_string(), 2u16, vec![3.5f32], Some(4i64));
+        let encoded = to_vec(expected.clone()).unwrap();
+        assert_eq!(
+            crate::serde::from_slice::<(String, u16, Vec<f32>, Option<i64>)>(&encoded).unwrap(),
+            expected
+        );
+        let encoded = crate::serde::to_vec(&expected).unwrap();
+        assert_eq!(
+            from_slice::<(String, u16, Vec<f32>, Option<i64>)>((), &encoded).unwrap(),
+            expected
+        );
+
+        let encoded = to_vec(Array::I16(vec![1, -2])).unwrap();
+        assert_eq!(
+            crate::serde::from_slice::<Vec<i32>>(&encoded).unwrap(),
+            vec![1, -2]
+        );
+
+        let encoded = crate::serde::to_vec(&Shape::Empty).unwrap();
+        let err = crate::serde::from_slice::<Record>(&encoded).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidType);
+
+        let encoded = crate::serde::to_vec(&(1u8, 2u8, 3u8)).unwrap();
+        let err = crate::serde::from_slice::<(u8, u8)>(&encoded).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidLength);
+
+        let encoded = to_vec("Circle").unwrap();
+        assert!(crate::serde::from_slice::<Shape>(&encoded).is_err());
+
+        let expected = (i128::MIN, u128::MAX);
+        let encoded = crate::serde::to_vec(&expected).unwrap();
+        assert_eq!(
+            crate::serde::from_slice::<(i128, u128)>(&encoded).unwrap(),
+            expected
+        );
+        assert_eq!(
+            from_slice::<Value>((), &encoded).unwrap(),
+            Value::List(vec![Value::I128(i128::MIN), Value::U128(u128::MAX)])
+        );
+
+        // the async functions enforce the given decoder options
+        let options = DecoderOptions::new().max_string_len(5);
+        let encoded = crate::serde::to_vec(&record).unwrap();
+        let err = crate::serde::from_slice_with_options::<Record>(&encoded, options).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::LimitExceeded);
+
+        let stream = crate::serde::encode(&record).unwrap();
+        let err = crate::serde::try_decode_with_options::<Record, _, _>(stream, options)
+            .await
+            .unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::LimitExceeded);
+
+        #[cfg(feature = "tokio-io")]
+        {
+            let err = crate::serde::read_from_with_options::<_, Record>(&encoded[..], options)
+                .await
+                .unwrap_err();
+            assert_eq!(err.kind(), ErrorKind::LimitExceeded);
+
+            let decoded: Record = crate::serde::read_from(&encoded[..]).await.unwrap();
+            assert_eq!(decoded, record);
+        }
     }
 }
@@ -0,0 +1,758 @@
+//! Encode and decode types which implement [`serde::Serialize`] and [`serde::Deserialize`].
+//!
+//! Example:
+//! ```
+//! # use serde_derive::{Deserialize, Serialize};
+//! #[derive(Debug, PartialEq, Serialize, Deserialize)]
+//! struct Layer {
+//!     name: String,
+//!     shape: (u32, u32),
+//!     bias: Option<f32>,
+//! }
+//!
+//! let expected = Layer { name: "dense".into(), shape: (3, 4), bias: None };
+//! let encoded = tbon::serde::to_vec(&expected).unwrap();
+//! let actual: Layer = tbon::serde::from_slice(&encoded).unwrap();
+//! assert_eq!(expected, actual);
+//! ```
+//!
+//! serde's data model maps onto TBON like this:
+//!  - `bool`, integers, and floats are encoded as the TBON scalar of the same type
+//!  - `char` and strings are encoded as strings
+//!  - `serialize_bytes` (e.g. `serde_bytes::ByteBuf`) is encoded as a `u8` array
+//!  - `None`, `()`, and unit structs are encoded as `None`
+//!  - `Some(value)` and newtype structs are encoded as the value they contain
+//!  - sequences, tuples, and tuple structs are encoded as lists
+//!  - maps are encoded as maps, and structs as maps keyed by field name
+//!
+//! Enum variants are externally tagged: a unit variant is encoded as the string of its name,
+//! and any other variant as a map with a single entry whose key is the name of the variant.
+//! For example, `Shape::Square(2)` is encoded as `{"Square": 2}` and
+//! `Shape::Rect { w: 2, h: 3 }` as `{"Rect": {"w": 2, "h": 3}}`.
+//!
+//! A value is encoded via, or decoded into, an intermediate [`Value`], so the async functions
+//! in this module buffer the entire value in memory. Use the `*_with_options` functions to
+//! enforce [`DecoderOptions`] when decoding from an untrusted source.
+
+use std::fmt;
+
+use ::serde::de::value::{MapDeserializer, SeqDeserializer};
+use ::serde::de::{
+    self as serde_de, DeserializeOwned, DeserializeSeed, EnumAccess, IntoDeserializer,
+    VariantAccess, Visitor,
+};
+use ::serde::forward_to_deserialize_any;
+use ::serde::ser::{self as serde_en, Serialize};
+use bytes::Bytes;
+use destream::IntoStream;
+use futures::stream::Stream;
+
+#[cfg(feature = "tokio-io")]
+use tokio::io::{AsyncRead, AsyncWrite};
+
+use super::de::{self, DecoderOptions, Error as DecodeError, ErrorKind};
+use super::en::{self, Error as EncodeError};
+use super::value::{Array, Value};
+
+impl serde_en::Error for EncodeError {
+    fn custom<T: fmt::Display>(msg: T) -> Self {
+        destream::en::Error::custom(msg)
+    }
+}
+
+impl serde_de::Error for DecodeError {
+    fn custom<T: fmt::Display>(msg: T) -> Self {
+        Self::new(ErrorKind::Custom, msg)
+    }
+
+    fn invalid_type(unexp: serde_de::Unexpected, exp: &dyn serde_de::Expected) -> Self {
+        let message = format!("invalid type: {}, expected {}", unexp, exp);
+        Self::new(ErrorKind::InvalidType, message)
+    }
+
+    fn invalid_length(len: usize, exp: &dyn serde_de::Expected) -> Self {
+        let message = format!("invalid length: {}, expected {}", len, exp);
+        Self::new(ErrorKind::InvalidLength, message)
+    }
+}
+
+/// Convert the given [`Serialize`] value into a [`Value`].
+pub fn to_value<T: Serialize + ?Sized>(value: &T) -> Result<Value, EncodeError> {
+    value.serialize(ValueSerializer)
+}
+
+/// Convert the given [`Value`] into an instance of `T`.
+pub fn from_value<T: DeserializeOwned>(value: Value) -> Result<T, DecodeError> {
+    T::deserialize(value)
+}
+
+/// Encode the given [`Serialize`] value into a [`Vec`] of bytes.
+pub fn to_vec<T: Serialize + ?Sized>(value: &T) -> Result<Vec<u8>, EncodeError> {
+    to_value(value).and_then(en::to_vec)
+}
+
+/// Decode an instance of `T` from the given TBON-encoded slice.
+pub fn from_slice<T: DeserializeOwned>(slice: &[u8]) -> Result<T, DecodeError> {
+    from_slice_with_options(slice, DecoderOptions::default())
+}
+
+/// Decode an instance of `T` from the given TBON-encoded slice,
+/// enforcing the limits set in the given `options`.
+pub fn from_slice_with_options<T: DeserializeOwned>(
+    slice: &[u8],
+    options: DecoderOptions,
+) -> Result<T, DecodeError> {
+    de::from_slice_with_options((), slice, options).and_then(from_value)
+}
+
+/// Encode the given [`Serialize`] value as a stream of TBON-encoded bytes.
+///
+/// The whole value is converted into a [`Value`] before the stream begins.
+pub fn encode<T: Serialize + ?Sized>(
+    value: &T,
+) -> Result<impl Stream<Item = Result<Bytes, EncodeError>> + Send + Unpin + 'static, EncodeError> {
+    to_value(value).and_then(|value| value.into_stream(en::Encoder::new()))
+}
+
+/// Decode an instance of `T` from the given stream of TBON-encoded bytes.
+///
+/// The whole value is buffered as a [`Value`] before it's converted into a `T`.
+pub async fn try_decode<T, E, S>(source: S) -> Result<T, DecodeError>
+where
+    T: DeserializeOwned,
+    E: fmt::Display,
+    S: Stream<Item = Result<Bytes, E>> + Send + Unpin,
+{
+    try_decode_with_options(source, DecoderOptions::default()).await
+}
+
+/// Decode an instance of `T` from the given stream of TBON-encoded bytes,
+/// enforcing the limits set in the given `options`.
+///
+/// The whole value is buffered as a [`Value`], within those limits, before it's converted
+/// into a `T`.
+pub async fn try_decode_with_options<T, E, S>(
+    source: S,
+    options: DecoderOptions,
+) -> Result<T, DecodeError>
+where
+    T: DeserializeOwned,
+    E: fmt::Display,
+    S: Stream<Item = Result<Bytes, E>> + Send + Unpin,
+{
+    de::try_decode_with_options((), source, options)
+        .await
+        .and_then(from_value)
+}
+
+/// Encode the given [`Serialize`] value into the given [`AsyncWrite`] sink.
+///
+/// The whole value is converted into a [`Value`] before any of it is written.
+#[cfg(feature = "tokio-io")]
+pub async fn write_to<W, T>(writer: W, value: &T) -> Result<(), EncodeError>
+where
+    W: AsyncWrite + Unpin,
+    T: Serialize + ?Sized,
+{
+    en::write_to(writer, to_value(value)?).await
+}
+
+/// Decode an instance of `T` from the given [`AsyncRead`] source.
+///
+/// The whole value is read into a [`Value`] before it's converted into a `T`.
+#[cfg(feature = "tokio-io")]
+pub async fn read_from<R, T>(source: R) -> Result<T, DecodeError>
+where
+    R: AsyncRead + Send + Unpin,
+    T: DeserializeOwned,
+{
+    read_from_with_options(source, DecoderOptions::default()).await
+}
+
+/// Decode an instance of `T` from the given [`AsyncRead`] source,
+/// enforcing the limits set in the given `options`.
+///
+/// The whole value is read into a [`Value`], within those limits, before it's converted
+/// into a `T`.
+#[cfg(feature = "tokio-io")]
+pub async fn read_from_with_options<R, T>(
+    source: R,
+    options: DecoderOptions,
+) -> Result<T, DecodeError>
+where
+    R: AsyncRead + Send + Unpin,
+    T: DeserializeOwned,
+{
+    de::read_from_with_options((), source, options)
+        .await
+        .and_then(from_value)
+}
+
+struct ValueSerializer;
+
+impl serde_en::Serializer for ValueSerializer {
+    type Ok = Value;
+    type Error = EncodeError;
+
+    type SerializeSeq = SerializeList;
+    type SerializeTuple = SerializeList;
+    type SerializeTupleStruct = SerializeList;
+    type SerializeTupleVariant = SerializeVariant<SerializeList>;
+    type SerializeMap = SerializeMap;
+    type SerializeStruct = SerializeMap;
+    type SerializeStructVariant = SerializeVariant<SerializeMap>;
+
+    fn serialize_bool(self, v: bool) -> Result<Value, EncodeError> {
+        Ok(Value::Bool(v))
+    }
+
+    fn serialize_i8(self, v: i8) -> Result<Value, EncodeError> {
+        Ok(Value::I8(v))
+    }
+
+    fn serialize_i16(self, v: i16) -> Result<Value, EncodeError> {
+        Ok(Value::I16(v))
+    }
+
+    fn serialize_i32(self, v: i32) -> Result<Value, EncodeError> {
+        Ok(Value::I32(v))
+    }
+
+    fn serialize_i64(self, v: i64) -> Result<Value, EncodeError> {
+        Ok(Value::I64(v))
+    }
+
+    fn serialize_i128(self, v: i128) -> Result<Value, EncodeError> {
+        Ok(Value::I128(v))
+    }
+
+    fn serialize_u8(self, v: u8) -> Result<Value, EncodeError> {
+        Ok(Value::U8(v))
+    }
+
+    fn serialize_u16(self, v: u16) -> Result<Value, EncodeError> {
+        Ok(Value::U16(v))
+    }
+
+    fn serialize_u32(self, v: u32) -> Result<Value, EncodeError> {
+        Ok(Value::U32(v))
+    }
+
+    fn serialize_u64(self, v: u64) -> Result<Value, EncodeError> {
+        Ok(Value::U64(v))
+    }
+
+    fn serialize_u128(self, v: u128) -> Result<Value, EncodeError> {
+        Ok(Value::U128(v))
+    }
+
+    fn serialize_f32(self, v: f32) -> Result<Value, EncodeError> {
+        Ok(Value::F32(v))
+    }
+
+    fn serialize_f64(self, v: f64) -> Result<Value, EncodeError> {
+        Ok(Value::F64(v))
+    }
+
+    fn serialize_char(self, v: char) -> Result<Value, EncodeError> {
+        Ok(Value::String(v.to_string()))
+    }
+
+    fn serialize_str(self, v: &str) -> Result<Value, EncodeError> {
+        Ok(Value::String(v.to_string()))
+    }
+
+    fn serialize_bytes(self, v: &[u8]) -> Result<Value, EncodeError> {
+        Ok(Value::Array(Array::U8(v.to_vec())))
+    }
+
+    fn serialize_none(self) -> Result<Value, EncodeError> {
+        Ok(Value::None)
+    }
+
+    fn serialize_some<T: Serialize + ?Sized>(self, value: &T) -> Result<Value, EncodeError> {
+        value.serialize(self)
+    }
+
+    fn serialize_unit(self) -> Result<Value, EncodeError> {
+        Ok(Value::None)
+    }
+
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<Value, EncodeError> {
+        Ok(Value::None)
+    }
+
+    fn serialize_unit_variant(
+        self,
+        _name: &'static str,
+        _index: u32,
+        variant: &'static str,
+    ) -> Result<Value, EncodeError> {
+        Ok(Value::String(variant.to_string()))
+    }
+
+    fn serialize_newtype_struct<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        value: &T,
+    ) -> Result<Value, EncodeError> {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        _index: u32,
+        variant: &'static str,
+        value: &T,
+    ) -> Result<Value, EncodeError> {
+        let value = value.serialize(self)?;
+        Ok(Value::Map(vec![(variant.into(), value)]))
+    }
+
+    fn serialize_seq(self, len: Option<usize>) -> Result<SerializeList, EncodeError> {
+        Ok(SerializeList::new(len))
+    }
+
+    fn serialize_tuple(self, len: usize) -> Result<SerializeList, EncodeError> {
+        Ok(SerializeList::new(Some(len)))
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        _name: &'static str,
+        len: usize,
+    ) -> Result<SerializeList, EncodeError> {
+        Ok(SerializeList::new(Some(len)))
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _index: u32,
+        variant: &'static str,
+        len: usize,
+    ) -> Result<SerializeVariant<SerializeList>, EncodeError> {
+        Ok(SerializeVariant {
+            variant,
+            inner: SerializeList::new(Some(len)),
+        })
+    }
+
+    fn serialize_map(self, len: Option<usize>) -> Result<SerializeMap, EncodeError> {
+        Ok(SerializeMap::new(len))
+    }
+
+    fn serialize_struct(
+        self,
+        _name: &'static str,
+        len: usize,
+    ) -> Result<SerializeMap, EncodeError> {
+        Ok(SerializeMap::new(Some(len)))
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _index: u32,
+        variant: &'static str,
+        len: usize,
+    ) -> Result<SerializeVariant<SerializeMap>, EncodeError> {
+        Ok(SerializeVariant {
+            variant,
+            inner: SerializeMap::new(Some(len)),
+        })
+    }
+}
+
+struct SerializeList {
+    items: Vec<Value>,
+}
+
+impl SerializeList {
+    fn new(len: Option<usize>) -> Self {
+        Self {
+            items: Vec::with_capacity(len.unwrap_or_default()),
+        }
+    }
+
+    fn push<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), EncodeError> {
+        let value = value.serialize(ValueSerializer)?;
+        self.items.push(value);
+        Ok(())
+    }
+
+    fn end(self) -> Value {
+        Value::List(self.items)
+    }
+}
+
+impl serde_en::SerializeSeq for SerializeList {
+    type Ok = Value;
+    type Error = EncodeError;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), EncodeError> {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<Value, EncodeError> {
+        Ok(SerializeList::end(self))
+    }
+}
+
+impl serde_en::SerializeTuple for SerializeList {
+    type Ok = Value;
+    type Error = EncodeError;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), EncodeError> {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<Value, EncodeError> {
+        Ok(SerializeList::end(self))
+    }
+}
+
+impl serde_en::SerializeTupleStruct for SerializeList {
+    type Ok = Value;
+    type Error = EncodeError;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), EncodeError> {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<Value, EncodeError> {
+        Ok(SerializeList::end(self))
+    }
+}
+
+struct SerializeMap {
+    entries: Vec<(Value, Value)>,
+    pending_key: Option<Value>,
+}
+
+impl SerializeMap {
+    fn new(len: Option<usize>) -> Self {
+        Self {
+            entries: Vec::with_capacity(len.unwrap_or_default()),
+            pending_key: None,
+        }
+    }
+
+    fn push<T: Serialize + ?Sized>(&mut self, key: &str, value: &T) -> Result<(), EncodeError> {
+        let value = value.serialize(ValueSerializer)?;
+        self.entries.push((key.into(), value));
+        Ok(())
+    }
+
+    fn end(self) -> Value {
+        Value::Map(self.entries)
+    }
+}
+
+impl serde_en::SerializeMap for SerializeMap {
+    type Ok = Value;
+    type Error = EncodeError;
+
+    fn serialize_key<T: Serialize + ?Sized>(&mut self, key: &T) -> Result<(), EncodeError> {
+        if self.pending_key.is_none() {
+            self.pending_key = Some(key.serialize(ValueSerializer)?);
+            Ok(())
+        } else {
+            Err(serde_en::Error::custom(
+                "You must call serialize_value before calling serialize_key again",
+            ))
+        }
+    }
+
+    fn serialize_value<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), EncodeError> {
+        let key = self.pending_key.take().ok_or_else(|| {
+            <EncodeError as serde_en::Error>::custom(
+                "You must call serialize_key before serialize_value",
+            )
+        })?;
+
+        let value = value.serialize(ValueSerializer)?;
+        self.entries.push((key, value));
+        Ok(())
+    }
+
+    fn end(self) -> Result<Value, EncodeError> {
+        if self.pending_key.is_some() {
+            Err(serde_en::Error::custom(
+                "You must call serialize_value after calling serialize_key",
+            ))
+        } else {
+            Ok(SerializeMap::end(self))
+        }
+    }
+}
+
+impl serde_en::SerializeStruct for SerializeMap {
+    type Ok = Value;
+    type Error = EncodeError;
+
+    fn serialize_field<T: Serialize + ?Sized>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<(), EncodeError> {
+        self.push(key, value)
+    }
+
+    fn end(self) -> Result<Value, EncodeError> {
+        Ok(SerializeMap::end(self))
+    }
+}
+
+/// Serializes the content of an enum variant, to encode as `{variant: content}`
+struct SerializeVariant<S> {
+    variant: &'static str,
+    inner: S,
+}
+
+impl serde_en::SerializeTupleVariant for SerializeVariant<SerializeList> {
+    type Ok = Value;
+    type Error = EncodeError;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), EncodeError> {
+        self.inner.push(value)
+    }
+
+    fn end(self) -> Result<Value, EncodeError> {
+        Ok(Value::Map(vec![(self.variant.into(), self.inner.end())]))
+    }
+}
+
+impl serde_en::SerializeStructVariant for SerializeVariant<SerializeMap> {
+    type Ok = Value;
+    type Error = EncodeError;
+
+    fn serialize_field<T: Serialize + ?Sized>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<(), EncodeError> {
+        self.inner.push(key, value)
+    }
+
+    fn end(self) -> Result<Value, EncodeError> {
+        Ok(Value::Map(vec![(self.variant.into(), self.inner.end())]))
+    }
+}
+
+impl<'de> IntoDeserializer<'de, DecodeError> for Value {
+    type Deserializer = Self;
+
+    fn into_deserializer(self) -> Self {
+        self
+    }
+}
+
+impl<'de> serde_de::Deserializer<'de> for Value {
+    type Error = DecodeError;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, DecodeError> {
+        match self {
+            Self::None => visitor.visit_unit(),
+            Self::Bool(v) => visitor.visit_bool(v),
+            #[cfg(feature = "half")]
+            Self::F16(v) => visitor.visit_f32(v.to_f32()),
+            #[cfg(feature = "half")]
+            Self::BF16(v) => visitor.visit_f32(v.to_f32()),
+            Self::F32(v) => visitor.visit_f32(v),
+            Self::F64(v) => visitor.visit_f64(v),
+            Self::I8(v) => visitor.visit_i8(v),
+            Self::I16(v) => visitor.visit_i16(v),
+            Self::I32(v) => visitor.visit_i32(v),
+            Self::I64(v) => visitor.visit_i64(v),
+            Self::I128(v) => visitor.visit_i128(v),
+            Self::U8(v) => visitor.visit_u8(v),
+            Self::U16(v) => visitor.visit_u16(v),
+            Self::U32(v) => visitor.visit_u32(v),
+            Self::U64(v) => visitor.visit_u64(v),
+            Self::U128(v) => visitor.visit_u128(v),
+            Self::String(v) => visitor.visit_string(v),
+            Self::Uuid(v) => visit_seq(v.as_bytes().to_vec(), visitor),
+            Self::Array(array) => match array {
+                Array::Bool(array) => visit_seq(array, visitor),
+                #[cfg(feature = "half")]
+                Array::F16(array) => visit_seq(widen(array), visitor),
+                #[cfg(feature = "half")]
+                Array::BF16(array) => visit_seq(widen(array), visitor),
+                Array::F32(array) => visit_seq(array, visitor),
+                Array::F64(array) => visit_seq(array, visitor),
+                Array::I8(array) => visit_seq(array, visitor),
+                Array::I16(array) => visit_seq(array, visitor),
+                Array::I32(array) => visit_seq(array, visitor),
+                Array::I64(array) => visit_seq(array, visitor),
+                Array::I128(array) => visit_seq(array, visitor),
+                Array::U8(array) => visit_seq(array, visitor),
+                Array::U16(array) => visit_seq(array, visitor),
+                Array::U32(array) => visit_seq(array, visitor),
+                Array::U64(array) => visit_seq(array, visitor),
+                Array::U128(array) => visit_seq(array, visitor),
+            },
+            Self::List(items) => visit_seq(items, visitor),
+            Self::Map(entries) => {
+                let mut map = MapDeserializer::new(entries.into_iter());
+                let value = visitor.visit_map(&mut map)?;
+                map.end()?;
+                Ok(value)
+            }
+        }
+    }
+
+    fn deserialize_bytes<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, DecodeError> {
+        match self {
+            Self::Array(Array::U8(bytes)) => visitor.visit_byte_buf(bytes),
+            Self::Uuid(uuid) => visitor.visit_bytes(uuid.as_bytes()),
+            other => other.deserialize_any(visitor),
+        }
+    }
+
+    fn deserialize_byte_buf<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, DecodeError> {
+        self.deserialize_bytes(visitor)
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, DecodeError> {
+        match self {
+            Self::None => visitor.visit_none(),
+            other => visitor.visit_some(other),
+        }
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, DecodeError> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, DecodeError> {
+        match self {
+            Self::String(variant) => visitor.visit_enum(variant.into_deserializer()),
+            Self::Map(mut entries) if entries.len() == 1 => {
+                let (variant, content) = entries.pop().expect("variant");
+                visitor.visit_enum(Variant { variant, content })
+            }
+            other => Err(serde_de::Error::invalid_type(
+                unexpected(&other),
+                &"a string or a map with a single entry",
+            )),
+        }
+    }
+
+    forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        unit unit_struct seq tuple tuple_struct map struct identifier ignored_any
+    }
+}
+
+fn visit_seq<'de, T, V>(items: Vec<T>, visitor: V) -> Result<V::Value, DecodeError>
+where
+    T: IntoDeserializer<'de, DecodeError>,
+    V: Visitor<'de>,
+{
+    let mut seq = SeqDeserializer::new(items.into_iter());
+    let value = visitor.visit_seq(&mut seq)?;
+    seq.end()?;
+    Ok(value)
+}
+
+/// Widen an array of 16-bit floats to `f32`s, since serde has no 16-bit float type.
+#[cfg(feature = "half")]
+fn widen<F: Into<f32>>(array: Vec<F>) -> Vec<f32> {
+    array.into_iter().map(Into::into).collect()
+}
+
+fn unexpected(value: &Value) -> serde_de::Unexpected<'_> {
+    use serde_de::Unexpected;
+
+    match value {
+        Value::None => Unexpected::Unit,
+        Value::Bool(v) => Unexpected::Bool(*v),
+        #[cfg(feature = "half")]
+        Value::F16(v) => Unexpected::Float(v.to_f64()),
+        #[cfg(feature = "half")]
+        Value::BF16(v) => Unexpected::Float(v.to_f64()),
+        Value::F32(v) => Unexpected::Float(*v as f64),
+        Value::F64(v) => Unexpected::Float(*v),
+        Value::I8(v) => Unexpected::Signed(*v as i64),
+        Value::I16(v) => Unexpected::Signed(*v as i64),
+        Value::I32(v) => Unexpected::Signed(*v as i64),
+        Value::I64(v) => Unexpected::Signed(*v),
+        Value::I128(_) => Unexpected::Other("128-bit integer"),
+        Value::U8(v) => Unexpected::Unsigned(*v as u64),
+        Value::U16(v) => Unexpected::Unsigned(*v as u64),
+        Value::U32(v) => Unexpected::Unsigned(*v as u64),
+        Value::U64(v) => Unexpected::Unsigned(*v),
+        Value::U128(_) => Unexpected::Other("128-bit unsigned integer"),
+        Value::String(v) => Unexpected::Str(v),
+        Value::Uuid(_) => Unexpected::Other("UUID"),
+        Value::Array(_) | Value::List(_) => Unexpected::Seq,
+        Value::Map(_) => Unexpected::Map,
+    }
+}
+
+/// The name and content of an enum variant encoded as `{variant: content}`
+struct Variant {
+    variant: Value,
+    content: Value,
+}
+
+impl<'de> EnumAccess<'de> for Variant {
+    type Error = DecodeError;
+    type Variant = Value;
+
+    fn variant_seed<V: DeserializeSeed<'de>>(
+        self,
+        seed: V,
+    ) -> Result<(V::Value, Value), DecodeError> {
+        let variant = seed.deserialize(self.variant)?;
+        Ok((variant, self.content))
+    }
+}
+
+impl<'de> VariantAccess<'de> for Value {
+    type Error = DecodeError;
+
+    fn unit_variant(self) -> Result<(), DecodeError> {
+        match self {
+            Self::None => Ok(()),
+            other => Err(serde_de::Error::invalid_type(
+                unexpected(&other),
+                &"a unit variant",
+            )),
+        }
+    }
+
+    fn newtype_variant_seed<T: DeserializeSeed<'de>>(
+        self,
+        seed: T,
+    ) -> Result<T::Value, DecodeError> {
+        seed.deserialize(self)
+    }
+
+    fn tuple_variant<V: Visitor<'de>>(
+        self,
+        _len: usize,
+        visitor: V,
+    ) -> Result<V::Value, DecodeError> {
+        serde_de::Deserializer::deserialize_seq(self, visitor)
+    }
+
+    fn struct_variant<V: Visitor<'de>>(
+        self,
+        _fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, DecodeError> {
+        serde_de::Deserializer::deserialize_map(self, visitor)
+    }
+}
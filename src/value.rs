@@ -0,0 +1,871 @@
+//! A dynamically-typed TBON [`Value`], for decoding a payload whose Rust type is not known
+//! ahead of time.
+
+use std::fmt;
+use std::ops::Index;
+
+use destream::{de, en, FromStream, IntoStream, ToStream};
+use futures::{future, stream};
+use uuid::Uuid;
+
+use super::de::{Native, VisitNative};
+use super::en::Encoder;
+
+static NONE: Value = Value::None;
+
+/// A typed TBON array
+#[derive(Clone, Debug, PartialEq)]
+pub enum Array {
+    Bool(Vec<bool>),
+    #[cfg(feature = "half")]
+    F16(Vec<half::f16>),
+    #[cfg(feature = "half")]
+    BF16(Vec<half::bf16>),
+    F32(Vec<f32>),
+    F64(Vec<f64>),
+    I8(Vec<i8>),
+    I16(Vec<i16>),
+    I32(Vec<i32>),
+    I64(Vec<i64>),
+    I128(Vec<i128>),
+    U8(Vec<u8>),
+    U16(Vec<u16>),
+    U32(Vec<u32>),
+    U64(Vec<u64>),
+    U128(Vec<u128>),
+}
+
+impl Array {
+    /// The name of the element type of this [`Array`], e.g. "u8".
+    pub fn dtype(&self) -> &'static str {
+        match self {
+            Self::Bool(_) => "bool",
+            #[cfg(feature = "half")]
+            Self::F16(_) => "f16",
+            #[cfg(feature = "half")]
+            Self::BF16(_) => "bf16",
+            Self::F32(_) => "f32",
+            Self::F64(_) => "f64",
+            Self::I8(_) => "i8",
+            Self::I16(_) => "i16",
+            Self::I32(_) => "i32",
+            Self::I64(_) => "i64",
+            Self::I128(_) => "i128",
+            Self::U8(_) => "u8",
+            Self::U16(_) => "u16",
+            Self::U32(_) => "u32",
+            Self::U64(_) => "u64",
+            Self::U128(_) => "u128",
+        }
+    }
+
+    /// The number of elements in this [`Array`].
+    pub fn len(&self) -> usize {
+        match self {
+            Self::Bool(array) => array.len(),
+            #[cfg(feature = "half")]
+            Self::F16(array) => array.len(),
+            #[cfg(feature = "half")]
+            Self::BF16(array) => array.len(),
+            Self::F32(array) => array.len(),
+            Self::F64(array) => array.len(),
+            Self::I8(array) => array.len(),
+            Self::I16(array) => array.len(),
+            Self::I32(array) => array.len(),
+            Self::I64(array) => array.len(),
+            Self::I128(array) => array.len(),
+            Self::U8(array) => array.len(),
+            Self::U16(array) => array.len(),
+            Self::U32(array) => array.len(),
+            Self::U64(array) => array.len(),
+            Self::U128(array) => array.len(),
+        }
+    }
+
+    /// Return `true` if this [`Array`] has no elements.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl<'en> IntoStream<'en> for Array {
+    fn into_stream<E: en::Encoder<'en>>(self, encoder: E) -> Result<E::Ok, E::Error> {
+        fn chunk<T>(array: Vec<T>) -> stream::Once<future::Ready<Vec<T>>> {
+            stream::once(future::ready(array))
+        }
+
+        match self {
+            Self::Bool(array) => encoder.encode_array_bool(chunk(array)),
+            #[cfg(feature = "half")]
+            Self::F16(array) => match Encoder::hook(encoder) {
+                Ok(hook) => hook.encode(|encoder| encoder.encode_array_f16(chunk(array))),
+                Err(encoder) => {
+                    let array = array.into_iter().map(half::f16::to_f32);
+                    encoder.encode_array_f32(stream::once(future::ready(array)))
+                }
+            },
+            #[cfg(feature = "half")]
+            Self::BF16(array) => match Encoder::hook(encoder) {
+                Ok(hook) => hook.encode(|encoder| encoder.encode_array_bf16(chunk(array))),
+                Err(encoder) => {
+                    let array = array.into_iter().map(half::bf16::to_f32);
+                    encoder.encode_array_f32(stream::once(future::ready(array)))
+                }
+            },
+            Self::F32(array) => encoder.encode_array_f32(chunk(array)),
+            Self::F64(array) => encoder.encode_array_f64(chunk(array)),
+            Self::I8(array) => encoder.encode_array_i8(chunk(array)),
+            Self::I16(array) => encoder.encode_array_i16(chunk(array)),
+            Self::I32(array) => encoder.encode_array_i32(chunk(array)),
+            Self::I64(array) => encoder.encode_array_i64(chunk(array)),
+            Self::I128(array) => match Encoder::hook(encoder) {
+                Ok(hook) => hook.encode(|encoder| encoder.encode_array_i128(chunk(array))),
+                Err(encoder) => encoder
+                    .encode_seq_stream(stream::iter(array.into_iter().map(|i| i.to_string()))),
+            },
+            Self::U8(array) => encoder.encode_array_u8(chunk(array)),
+            Self::U16(array) => encoder.encode_array_u16(chunk(array)),
+            Self::U32(array) => encoder.encode_array_u32(chunk(array)),
+            Self::U64(array) => encoder.encode_array_u64(chunk(array)),
+            Self::U128(array) => match Encoder::hook(encoder) {
+                Ok(hook) => hook.encode(|encoder| encoder.encode_array_u128(chunk(array))),
+                Err(encoder) => encoder
+                    .encode_seq_stream(stream::iter(array.into_iter().map(|u| u.to_string()))),
+            },
+        }
+    }
+}
+
+impl<'en> ToStream<'en> for Array {
+    fn to_stream<E: en::Encoder<'en>>(&'en self, encoder: E) -> Result<E::Ok, E::Error> {
+        fn chunk<T: Copy>(
+            array: &[T],
+        ) -> stream::Once<future::Ready<std::iter::Copied<std::slice::Iter<'_, T>>>> {
+            stream::once(future::ready(array.iter().copied()))
+        }
+
+        match self {
+            Self::Bool(array) => encoder.encode_array_bool(chunk(array)),
+            #[cfg(feature = "half")]
+            Self::F16(array) => match Encoder::hook(encoder) {
+                Ok(hook) => hook.encode(|encoder| encoder.encode_array_f16(chunk(array))),
+                Err(encoder) => {
+                    let array = array.iter().map(|f| f.to_f32());
+                    encoder.encode_array_f32(stream::once(future::ready(array)))
+                }
+            },
+            #[cfg(feature = "half")]
+            Self::BF16(array) => match Encoder::hook(encoder) {
+                Ok(hook) => hook.encode(|encoder| encoder.encode_array_bf16(chunk(array))),
+                Err(encoder) => {
+                    let array = array.iter().map(|f| f.to_f32());
+                    encoder.encode_array_f32(stream::once(future::ready(array)))
+                }
+            },
+            Self::F32(array) => encoder.encode_array_f32(chunk(array)),
+            Self::F64(array) => encoder.encode_array_f64(chunk(array)),
+            Self::I8(array) => encoder.encode_array_i8(chunk(array)),
+            Self::I16(array) => encoder.encode_array_i16(chunk(array)),
+            Self::I32(array) => encoder.encode_array_i32(chunk(array)),
+            Self::I64(array) => encoder.encode_array_i64(chunk(array)),
+            Self::I128(array) => match Encoder::hook(encoder) {
+                Ok(hook) => hook.encode(|encoder| encoder.encode_array_i128(chunk(array))),
+                Err(encoder) => {
+                    encoder.encode_seq_stream(stream::iter(array.iter().map(i128::to_string)))
+                }
+            },
+            Self::U8(array) => encoder.encode_array_u8(chunk(array)),
+            Self::U16(array) => encoder.encode_array_u16(chunk(array)),
+            Self::U32(array) => encoder.encode_array_u32(chunk(array)),
+            Self::U64(array) => encoder.encode_array_u64(chunk(array)),
+            Self::U128(array) => match Encoder::hook(encoder) {
+                Ok(hook) => hook.encode(|encoder| encoder.encode_array_u128(chunk(array))),
+                Err(encoder) => {
+                    encoder.encode_seq_stream(stream::iter(array.iter().map(u128::to_string)))
+                }
+            },
+        }
+    }
+}
+
+impl fmt::Display for Array {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fn write<T: fmt::Display>(f: &mut fmt::Formatter, array: &[T]) -> fmt::Result {
+            for (i, element) in array.iter().enumerate() {
+                if i > 0 {
+                    f.write_str(", ")?;
+                }
+
+                write!(f, "{}", element)?;
+            }
+
+            Ok(())
+        }
+
+        write!(f, "{}[", self.dtype())?;
+
+        match self {
+            Self::Bool(array) => write(f, array),
+            #[cfg(feature = "half")]
+            Self::F16(array) => write(f, array),
+            #[cfg(feature = "half")]
+            Self::BF16(array) => write(f, array),
+            Self::F32(array) => write(f, array),
+            Self::F64(array) => write(f, array),
+            Self::I8(array) => write(f, array),
+            Self::I16(array) => write(f, array),
+            Self::I32(array) => write(f, array),
+            Self::I64(array) => write(f, array),
+            Self::I128(array) => write(f, array),
+            Self::U8(array) => write(f, array),
+            Self::U16(array) => write(f, array),
+            Self::U32(array) => write(f, array),
+            Self::U64(array) => write(f, array),
+            Self::U128(array) => write(f, array),
+        }?;
+
+        f.write_str("]")
+    }
+}
+
+/// Any value which can be encoded as TBON
+///
+/// A variable-length integer is decoded as a [`Value::I64`] or [`Value::U64`], or as a
+/// [`Value::I128`] or [`Value::U128`] if it was encoded from a 128-bit integer, so it only
+/// re-encodes to the same bytes with [`crate::en::EncoderOptions::compact_integers`].
+#[derive(Clone, Debug, Default, PartialEq)]
+pub enum Value {
+    #[default]
+    None,
+    Bool(bool),
+    #[cfg(feature = "half")]
+    F16(half::f16),
+    #[cfg(feature = "half")]
+    BF16(half::bf16),
+    F32(f32),
+    F64(f64),
+    I8(i8),
+    I16(i16),
+    I32(i32),
+    I64(i64),
+    I128(i128),
+    U8(u8),
+    U16(u16),
+    U32(u32),
+    U64(u64),
+    U128(u128),
+    String(String),
+    Uuid(Uuid),
+    Array(Array),
+    List(Vec<Value>),
+    /// A map, whose entries are kept in the order they were encoded in
+    Map(Vec<(Value, Value)>),
+}
+
+impl Value {
+    /// Return `true` if this is [`Value::None`].
+    pub fn is_none(&self) -> bool {
+        matches!(self, Self::None)
+    }
+
+    /// Return this value as a `bool`, if it is one.
+    pub fn as_bool(&self) -> Option<bool> {
+        match self {
+            Self::Bool(b) => Some(*b),
+            _ => None,
+        }
+    }
+
+    /// Return this value as an `f64`, if it is any floating-point number.
+    pub fn as_f64(&self) -> Option<f64> {
+        match self {
+            #[cfg(feature = "half")]
+            Self::F16(f) => Some(f.to_f64()),
+            #[cfg(feature = "half")]
+            Self::BF16(f) => Some(f.to_f64()),
+            Self::F32(f) => Some(*f as f64),
+            Self::F64(f) => Some(*f),
+            _ => None,
+        }
+    }
+
+    /// Return this value as an `i64`, if it is any integer within the range of an `i64`.
+    pub fn as_i64(&self) -> Option<i64> {
+        match self {
+            Self::I8(i) => Some(*i as i64),
+            Self::I16(i) => Some(*i as i64),
+            Self::I32(i) => Some(*i as i64),
+            Self::I64(i) => Some(*i),
+            Self::I128(i) => i64::try_from(*i).ok(),
+            Self::U8(u) => Some(*u as i64),
+            Self::U16(u) => Some(*u as i64),
+            Self::U32(u) => Some(*u as i64),
+            Self::U64(u) => i64::try_from(*u).ok(),
+            Self::U128(u) => i64::try_from(*u).ok(),
+            _ => None,
+        }
+    }
+
+    /// Return this value as a `u64`, if it is any integer within the range of a `u64`.
+    pub fn as_u64(&self) -> Option<u64> {
+        match self {
+            Self::I8(i) => u64::try_from(*i).ok(),
+            Self::I16(i) => u64::try_from(*i).ok(),
+            Self::I32(i) => u64::try_from(*i).ok(),
+            Self::I64(i) => u64::try_from(*i).ok(),
+            Self::I128(i) => u64::try_from(*i).ok(),
+            Self::U8(u) => Some(*u as u64),
+            Self::U16(u) => Some(*u as u64),
+            Self::U32(u) => Some(*u as u64),
+            Self::U64(u) => Some(*u),
+            Self::U128(u) => u64::try_from(*u).ok(),
+            _ => None,
+        }
+    }
+
+    /// Return this value as a `str`, if it is a string.
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Self::String(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    /// Return this value as a [`Uuid`], if it is one.
+    pub fn as_uuid(&self) -> Option<Uuid> {
+        match self {
+            Self::Uuid(uuid) => Some(*uuid),
+            _ => None,
+        }
+    }
+
+    /// Return this value as an [`Array`], if it is one.
+    pub fn as_array(&self) -> Option<&Array> {
+        match self {
+            Self::Array(array) => Some(array),
+            _ => None,
+        }
+    }
+
+    /// Return this value as a list, if it is one.
+    pub fn as_list(&self) -> Option<&[Value]> {
+        match self {
+            Self::List(list) => Some(list),
+            _ => None,
+        }
+    }
+
+    /// Return the entries of this value, if it is a map.
+    pub fn as_map(&self) -> Option<&[(Value, Value)]> {
+        match self {
+            Self::Map(map) => Some(map),
+            _ => None,
+        }
+    }
+
+    /// Look up an element of a list by position, or the value of a map entry by key.
+    ///
+    /// If a map has more than one entry with the given key, the first one is returned.
+    pub fn get<I: ValueIndex>(&self, index: I) -> Option<&Value> {
+        index.index_into(self)
+    }
+}
+
+/// A position or key which can be used to index into a [`Value`]
+pub trait ValueIndex {
+    /// Look up this index in the given `value`.
+    fn index_into(self, value: &Value) -> Option<&Value>;
+}
+
+impl ValueIndex for usize {
+    fn index_into(self, value: &Value) -> Option<&Value> {
+        value.as_list().and_then(|list| list.get(self))
+    }
+}
+
+impl ValueIndex for &str {
+    fn index_into(self, value: &Value) -> Option<&Value> {
+        let map = value.as_map()?;
+        map.iter()
+            .find(|(key, _)| key.as_str() == Some(self))
+            .map(|(_, value)| value)
+    }
+}
+
+impl ValueIndex for &Value {
+    fn index_into(self, value: &Value) -> Option<&Value> {
+        let map = value.as_map()?;
+        map.iter()
+            .find(|(key, _)| key == self)
+            .map(|(_, value)| value)
+    }
+}
+
+impl<I: ValueIndex> Index<I> for Value {
+    type Output = Value;
+
+    /// Look up an element of a list by position, or the value of a map entry by key,
+    /// returning [`Value::None`] if there is no such element.
+    fn index(&self, index: I) -> &Value {
+        self.get(index).unwrap_or(&NONE)
+    }
+}
+
+impl From<bool> for Value {
+    fn from(b: bool) -> Self {
+        Self::Bool(b)
+    }
+}
+
+#[cfg(feature = "half")]
+impl From<half::f16> for Value {
+    fn from(f: half::f16) -> Self {
+        Self::F16(f)
+    }
+}
+
+#[cfg(feature = "half")]
+impl From<half::bf16> for Value {
+    fn from(f: half::bf16) -> Self {
+        Self::BF16(f)
+    }
+}
+
+impl From<f32> for Value {
+    fn from(f: f32) -> Self {
+        Self::F32(f)
+    }
+}
+
+impl From<f64> for Value {
+    fn from(f: f64) -> Self {
+        Self::F64(f)
+    }
+}
+
+impl From<i8> for Value {
+    fn from(i: i8) -> Self {
+        Self::I8(i)
+    }
+}
+
+impl From<i16> for Value {
+    fn from(i: i16) -> Self {
+        Self::I16(i)
+    }
+}
+
+impl From<i32> for Value {
+    fn from(i: i32) -> Self {
+        Self::I32(i)
+    }
+}
+
+impl From<i64> for Value {
+    fn from(i: i64) -> Self {
+        Self::I64(i)
+    }
+}
+
+impl From<i128> for Value {
+    fn from(i: i128) -> Self {
+        Self::I128(i)
+    }
+}
+
+impl From<u8> for Value {
+    fn from(u: u8) -> Self {
+        Self::U8(u)
+    }
+}
+
+impl From<u16> for Value {
+    fn from(u: u16) -> Self {
+        Self::U16(u)
+    }
+}
+
+impl From<u32> for Value {
+    fn from(u: u32) -> Self {
+        Self::U32(u)
+    }
+}
+
+impl From<u64> for Value {
+    fn from(u: u64) -> Self {
+        Self::U64(u)
+    }
+}
+
+impl From<u128> for Value {
+    fn from(u: u128) -> Self {
+        Self::U128(u)
+    }
+}
+
+impl From<String> for Value {
+    fn from(s: String) -> Self {
+        Self::String(s)
+    }
+}
+
+impl From<&str> for Value {
+    fn from(s: &str) -> Self {
+        Self::String(s.to_string())
+    }
+}
+
+impl From<Uuid> for Value {
+    fn from(uuid: Uuid) -> Self {
+        Self::Uuid(uuid)
+    }
+}
+
+impl From<Array> for Value {
+    fn from(array: Array) -> Self {
+        Self::Array(array)
+    }
+}
+
+impl From<Vec<Value>> for Value {
+    fn from(list: Vec<Value>) -> Self {
+        Self::List(list)
+    }
+}
+
+pub(crate) struct ValueVisitor;
+
+impl ValueVisitor {
+    async fn read_array<T, A>(mut access: A) -> Result<Vec<T>, A::Error>
+    where
+        T: Copy + Default + Send,
+        A: de::ArrayAccess<T>,
+    {
+        let mut array = Vec::new();
+        let mut buffer = [T::default(); 4096];
+
+        loop {
+            let len = access.buffer(&mut buffer).await?;
+            if len == 0 {
+                break;
+            } else {
+                array.extend_from_slice(&buffer[..len]);
+            }
+        }
+
+        Ok(array)
+    }
+}
+
+impl de::Visitor for ValueVisitor {
+    type Value = Value;
+
+    fn expecting() -> &'static str {
+        "a TBON value"
+    }
+
+    fn visit_bool<E: de::Error>(self, v: bool) -> Result<Value, E> {
+        Ok(Value::Bool(v))
+    }
+
+    fn visit_i8<E: de::Error>(self, v: i8) -> Result<Value, E> {
+        Ok(Value::I8(v))
+    }
+
+    fn visit_i16<E: de::Error>(self, v: i16) -> Result<Value, E> {
+        Ok(Value::I16(v))
+    }
+
+    fn visit_i32<E: de::Error>(self, v: i32) -> Result<Value, E> {
+        Ok(Value::I32(v))
+    }
+
+    fn visit_i64<E: de::Error>(self, v: i64) -> Result<Value, E> {
+        Ok(Value::I64(v))
+    }
+
+    fn visit_u8<E: de::Error>(self, v: u8) -> Result<Value, E> {
+        Ok(Value::U8(v))
+    }
+
+    fn visit_u16<E: de::Error>(self, v: u16) -> Result<Value, E> {
+        Ok(Value::U16(v))
+    }
+
+    fn visit_u32<E: de::Error>(self, v: u32) -> Result<Value, E> {
+        Ok(Value::U32(v))
+    }
+
+    fn visit_u64<E: de::Error>(self, v: u64) -> Result<Value, E> {
+        Ok(Value::U64(v))
+    }
+
+    fn visit_f32<E: de::Error>(self, v: f32) -> Result<Value, E> {
+        Ok(Value::F32(v))
+    }
+
+    fn visit_f64<E: de::Error>(self, v: f64) -> Result<Value, E> {
+        Ok(Value::F64(v))
+    }
+
+    async fn visit_array_bool<A: de::ArrayAccess<bool>>(self, a: A) -> Result<Value, A::Error> {
+        Self::read_array(a).await.map(Array::Bool).map(Value::Array)
+    }
+
+    async fn visit_array_i8<A: de::ArrayAccess<i8>>(self, a: A) -> Result<Value, A::Error> {
+        Self::read_array(a).await.map(Array::I8).map(Value::Array)
+    }
+
+    async fn visit_array_i16<A: de::ArrayAccess<i16>>(self, a: A) -> Result<Value, A::Error> {
+        Self::read_array(a).await.map(Array::I16).map(Value::Array)
+    }
+
+    async fn visit_array_i32<A: de::ArrayAccess<i32>>(self, a: A) -> Result<Value, A::Error> {
+        Self::read_array(a).await.map(Array::I32).map(Value::Array)
+    }
+
+    async fn visit_array_i64<A: de::ArrayAccess<i64>>(self, a: A) -> Result<Value, A::Error> {
+        Self::read_array(a).await.map(Array::I64).map(Value::Array)
+    }
+
+    async fn visit_array_u8<A: de::ArrayAccess<u8>>(self, a: A) -> Result<Value, A::Error> {
+        Self::read_array(a).await.map(Array::U8).map(Value::Array)
+    }
+
+    async fn visit_array_u16<A: de::ArrayAccess<u16>>(self, a: A) -> Result<Value, A::Error> {
+        Self::read_array(a).await.map(Array::U16).map(Value::Array)
+    }
+
+    async fn visit_array_u32<A: de::ArrayAccess<u32>>(self, a: A) -> Result<Value, A::Error> {
+        Self::read_array(a).await.map(Array::U32).map(Value::Array)
+    }
+
+    async fn visit_array_u64<A: de::ArrayAccess<u64>>(self, a: A) -> Result<Value, A::Error> {
+        Self::read_array(a).await.map(Array::U64).map(Value::Array)
+    }
+
+    async fn visit_array_f32<A: de::ArrayAccess<f32>>(self, a: A) -> Result<Value, A::Error> {
+        Self::read_array(a).await.map(Array::F32).map(Value::Array)
+    }
+
+    async fn visit_array_f64<A: de::ArrayAccess<f64>>(self, a: A) -> Result<Value, A::Error> {
+        Self::read_array(a).await.map(Array::F64).map(Value::Array)
+    }
+
+    fn visit_string<E: de::Error>(self, v: String) -> Result<Value, E> {
+        Ok(Value::String(v))
+    }
+
+    fn visit_unit<E: de::Error>(self) -> Result<Value, E> {
+        Ok(Value::None)
+    }
+
+    fn visit_none<E: de::Error>(self) -> Result<Value, E> {
+        Ok(Value::None)
+    }
+
+    async fn visit_map<A: de::MapAccess>(self, mut access: A) -> Result<Value, A::Error> {
+        let mut map = Vec::with_capacity(access.size_hint().unwrap_or_default());
+
+        while let Some(key) = access.next_key::<Value>(()).await? {
+            let value = access.next_value::<Value>(()).await?;
+            map.push((key, value));
+        }
+
+        Ok(Value::Map(map))
+    }
+
+    async fn visit_seq<A: de::SeqAccess>(self, mut access: A) -> Result<Value, A::Error> {
+        let mut list = Vec::with_capacity(access.size_hint().unwrap_or_default());
+
+        while let Some(element) = access.next_element::<Value>(()).await? {
+            list.push(element);
+        }
+
+        Ok(Value::List(list))
+    }
+}
+
+impl VisitNative for ValueVisitor {
+    fn visit_native(self, native: Native) -> Value {
+        match native {
+            Native::I128(i) => Value::I128(i),
+            Native::U128(u) => Value::U128(u),
+            Native::ArrayI128(array) => Value::Array(Array::I128(array)),
+            Native::ArrayU128(array) => Value::Array(Array::U128(array)),
+            Native::Uuid(uuid) => Value::Uuid(uuid),
+            #[cfg(feature = "half")]
+            Native::F16(f) => Value::F16(f),
+            #[cfg(feature = "half")]
+            Native::BF16(f) => Value::BF16(f),
+            #[cfg(feature = "half")]
+            Native::ArrayF16(array) => Value::Array(Array::F16(array)),
+            #[cfg(feature = "half")]
+            Native::ArrayBF16(array) => Value::Array(Array::BF16(array)),
+        }
+    }
+}
+
+impl FromStream for Value {
+    type Context = ();
+
+    async fn from_stream<D: de::Decoder>(_: (), decoder: &mut D) -> Result<Self, D::Error> {
+        decoder.decode_any(ValueVisitor).await
+    }
+}
+
+impl<'en> IntoStream<'en> for Value {
+    fn into_stream<E: en::Encoder<'en>>(self, encoder: E) -> Result<E::Ok, E::Error> {
+        match self {
+            Self::None => encoder.encode_none(),
+            Self::Bool(b) => encoder.encode_bool(b),
+            #[cfg(feature = "half")]
+            Self::F16(f) => match Encoder::hook(encoder) {
+                Ok(hook) => hook.encode(|encoder| encoder.encode_f16(f)),
+                Err(encoder) => encoder.encode_f32(f.to_f32()),
+            },
+            #[cfg(feature = "half")]
+            Self::BF16(f) => match Encoder::hook(encoder) {
+                Ok(hook) => hook.encode(|encoder| encoder.encode_bf16(f)),
+                Err(encoder) => encoder.encode_f32(f.to_f32()),
+            },
+            Self::F32(f) => encoder.encode_f32(f),
+            Self::F64(f) => encoder.encode_f64(f),
+            Self::I8(i) => encoder.encode_i8(i),
+            Self::I16(i) => encoder.encode_i16(i),
+            Self::I32(i) => encoder.encode_i32(i),
+            Self::I64(i) => encoder.encode_i64(i),
+            Self::I128(i) => match Encoder::hook(encoder) {
+                Ok(hook) => hook.encode(|encoder| encoder.encode_i128(i)),
+                Err(encoder) => encoder.encode_str(&i.to_string()),
+            },
+            Self::U8(u) => encoder.encode_u8(u),
+            Self::U16(u) => encoder.encode_u16(u),
+            Self::U32(u) => encoder.encode_u32(u),
+            Self::U64(u) => encoder.encode_u64(u),
+            Self::U128(u) => match Encoder::hook(encoder) {
+                Ok(hook) => hook.encode(|encoder| encoder.encode_u128(u)),
+                Err(encoder) => encoder.encode_str(&u.to_string()),
+            },
+            Self::String(s) => encoder.encode_str(&s),
+            Self::Uuid(uuid) => encoder.encode_uuid(uuid),
+            Self::Array(array) => array.into_stream(encoder),
+            Self::List(list) => list.into_stream(encoder),
+            Self::Map(map) => {
+                use en::EncodeMap;
+
+                let mut encoder = encoder.encode_map(Some(map.len()))?;
+                for (key, value) in map {
+                    encoder.encode_entry(key, value)?;
+                }
+
+                encoder.end()
+            }
+        }
+    }
+}
+
+impl<'en> ToStream<'en> for Value {
+    fn to_stream<E: en::Encoder<'en>>(&'en self, encoder: E) -> Result<E::Ok, E::Error> {
+        match self {
+            Self::None => encoder.encode_none(),
+            Self::Bool(b) => encoder.encode_bool(*b),
+            #[cfg(feature = "half")]
+            Self::F16(f) => match Encoder::hook(encoder) {
+                Ok(hook) => hook.encode(|encoder| encoder.encode_f16(*f)),
+                Err(encoder) => encoder.encode_f32(f.to_f32()),
+            },
+            #[cfg(feature = "half")]
+            Self::BF16(f) => match Encoder::hook(encoder) {
+                Ok(hook) => hook.encode(|encoder| encoder.encode_bf16(*f)),
+                Err(encoder) => encoder.encode_f32(f.to_f32()),
+            },
+            Self::F32(f) => encoder.encode_f32(*f),
+            Self::F64(f) => encoder.encode_f64(*f),
+            Self::I8(i) => encoder.encode_i8(*i),
+            Self::I16(i) => encoder.encode_i16(*i),
+            Self::I32(i) => encoder.encode_i32(*i),
+            Self::I64(i) => encoder.encode_i64(*i),
+            Self::I128(i) => match Encoder::hook(encoder) {
+                Ok(hook) => hook.encode(|encoder| encoder.encode_i128(*i)),
+                Err(encoder) => encoder.encode_str(&i.to_string()),
+            },
+            Self::U8(u) => encoder.encode_u8(*u),
+            Self::U16(u) => encoder.encode_u16(*u),
+            Self::U32(u) => encoder.encode_u32(*u),
+            Self::U64(u) => encoder.encode_u64(*u),
+            Self::U128(u) => match Encoder::hook(encoder) {
+                Ok(hook) => hook.encode(|encoder| encoder.encode_u128(*u)),
+                Err(encoder) => encoder.encode_str(&u.to_string()),
+            },
+            Self::String(s) => encoder.encode_str(s),
+            Self::Uuid(uuid) => encoder.encode_uuid(*uuid),
+            Self::Array(array) => array.to_stream(encoder),
+            Self::List(list) => list.to_stream(encoder),
+            Self::Map(map) => {
+                use en::EncodeMap;
+
+                let mut encoder = encoder.encode_map(Some(map.len()))?;
+                for (key, value) in map {
+                    encoder.encode_entry(key, value)?;
+                }
+
+                encoder.end()
+            }
+        }
+    }
+}
+
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::None => f.write_str("None"),
+            Self::Bool(b) => fmt::Display::fmt(b, f),
+            #[cfg(feature = "half")]
+            Self::F16(n) => write!(f, "{}f16", n),
+            #[cfg(feature = "half")]
+            Self::BF16(n) => write!(f, "{}bf16", n),
+            Self::F32(n) => write!(f, "{}f32", n),
+            Self::F64(n) => write!(f, "{}f64", n),
+            Self::I8(n) => write!(f, "{}i8", n),
+            Self::I16(n) => write!(f, "{}i16", n),
+            Self::I32(n) => write!(f, "{}i32", n),
+            Self::I64(n) => write!(f, "{}i64", n),
+            Self::I128(n) => write!(f, "{}i128", n),
+            Self::U8(n) => write!(f, "{}u8", n),
+            Self::U16(n) => write!(f, "{}u16", n),
+            Self::U32(n) => write!(f, "{}u32", n),
+            Self::U64(n) => write!(f, "{}u64", n),
+            Self::U128(n) => write!(f, "{}u128", n),
+            Self::String(s) => fmt::Debug::fmt(s, f),
+            Self::Uuid(uuid) => fmt::Display::fmt(uuid, f),
+            Self::Array(array) => fmt::Display::fmt(array, f),
+            Self::List(list) => {
+                f.write_str("[")?;
+
+                for (i, item) in list.iter().enumerate() {
+                    if i > 0 {
+                        f.write_str(", ")?;
+                    }
+
+                    write!(f, "{}", item)?;
+                }
+
+                f.write_str("]")
+            }
+            Self::Map(map) => {
+                f.write_str("{")?;
+
+                for (i, (key, value)) in map.iter().enumerate() {
+                    if i > 0 {
+                        f.write_str(", ")?;
+                    }
+
+                    write!(f, "{}: {}", key, value)?;
+                }
+
+                f.write_str("}")
+            }
+        }
+    }
+}
@@ -0,0 +1,59 @@
+//! LEB128 variable-length encoding of unsigned integers, and zigzag encoding of signed integers.
+
+use bytes::BufMut;
+
+/// The maximum encoded length of a `u128`.
+pub const MAX_LEN: usize = 19;
+
+/// Append the LEB128 encoding of `n` to `output`.
+pub fn put<B: BufMut>(mut n: u128, output: &mut B) {
+    while n >= 0x80 {
+        output.put_u8((n as u8) | 0x80);
+        n >>= 7;
+    }
+
+    output.put_u8(n as u8);
+}
+
+/// The length of the LEB128 encoding of `n`.
+pub fn encoded_len(n: u128) -> usize {
+    let bits = 128 - n.leading_zeros() as usize;
+    Ord::max(1, bits.div_ceil(7))
+}
+
+/// Return the length of the varint at the start of `bytes`, if it ends within [`MAX_LEN`] bytes.
+pub fn len(bytes: &[u8]) -> Option<usize> {
+    bytes
+        .iter()
+        .take(MAX_LEN)
+        .position(|byte| byte & 0x80 == 0)
+        .map(|i| i + 1)
+}
+
+/// Decode a complete varint, or return `None` if its value does not fit in a `u128`.
+pub fn decode(bytes: &[u8]) -> Option<u128> {
+    let mut n = 0u128;
+
+    for (i, byte) in bytes.iter().enumerate() {
+        let bits = u128::from(byte & 0x7f);
+        let shift = 7 * i as u32;
+
+        if shift >= 128 || (bits << shift) >> shift != bits {
+            return None;
+        }
+
+        n |= bits << shift;
+    }
+
+    Some(n)
+}
+
+/// Map a signed integer to an unsigned integer such that values near zero remain small.
+pub fn zigzag(n: i128) -> u128 {
+    ((n << 1) ^ (n >> 127)) as u128
+}
+
+/// The inverse of [`zigzag`].
+pub fn unzigzag(n: u128) -> i128 {
+    ((n >> 1) as i128) ^ -((n & 1) as i128)
+}